@@ -0,0 +1,64 @@
+use allyourbase::Number;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// deterministic pseudo-random decimal digits
+fn decimal_digits(len: usize) -> Vec<u32> {
+    let mut state = 42u64;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            ((state >> 33) % 10) as u32
+        })
+        .collect()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decimal to binary");
+    group.sample_size(10);
+    for len in [100, 1000, 5000, 10_000, 50_000, 100_000].iter() {
+        let digits = decimal_digits(*len);
+        group.bench_with_input(
+            BenchmarkId::new("divide and conquer", len),
+            &digits,
+            |b, digits| b.iter(|| allyourbase::convert(black_box(digits), 10, 2).unwrap()),
+        );
+        // quadratic, takes too long on larger inputs
+        if *len <= 5000 {
+            group.bench_with_input(
+                BenchmarkId::new("power summation", len),
+                &digits,
+                |b, digits| b.iter(|| power_summation(black_box(digits), 10, 2)),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
+
+/// Previous implementation of `Number::convert`: every power of the source
+/// base is built in the target base and `digit * base^i` is summed up.
+fn power_summation(digits: &[u32], from_base: u32, to_base: u32) -> Vec<u32> {
+    let mut result = Number::new(to_base).unwrap();
+
+    // from_base as Number with base = to_base
+    let base_in_dest_base = Number::from_u32(to_base, from_base).unwrap();
+
+    // powers of from_base (as Number with base = to_base), starting from 0:
+    // 1, base, base^2, base^3,...
+    let base_powers = (0..).scan(Number::from_u32(to_base, 1).unwrap(), |pow, _| {
+        let res = pow.clone();
+        *pow *= &base_in_dest_base;
+        Some(res)
+    });
+
+    for (&digit, base_power) in digits.iter().rev().zip(base_powers) {
+        let digit_in_dest_base = base_power * digit;
+        result += &digit_in_dest_base;
+    }
+
+    result.digits()
+}
@@ -1,8 +1,37 @@
+pub use alphabet::Alphabet;
+pub use base_number::Number;
+pub use real::{Converted, Real};
+pub use stream::Stream;
+
 #[derive(Debug, PartialEq)]
 pub enum Error {
     InvalidInputBase,
     InvalidOutputBase,
-    InvalidDigit(u32),
+    /// Digit is not less than the base; position is the index of the digit
+    /// in the input slice, or the byte offset of its character in a string.
+    InvalidDigit {
+        digit: u32,
+        position: usize,
+    },
+    /// Character is not in the alphabet; position is its byte offset.
+    InvalidCharacter {
+        character: char,
+        position: usize,
+    },
+    /// Number does not fit into the output representation.
+    Overflow,
+    /// Bases are not powers of the same number, see `convert_stream`.
+    IncompatibleBases,
+    /// Mixed-radix number has more digits than positions, see `from_mixed_radix`.
+    TooManyDigits {
+        count: usize,
+        max: usize,
+    },
+    /// String has no digits, see `Number::from_str`.
+    NoDigits,
+    /// Alphabet has fewer than two symbols or a repeated symbol,
+    /// see `Alphabet::new`.
+    InvalidAlphabet,
 }
 
 ///
@@ -42,13 +71,609 @@ pub fn convert(digits: &[u32], from_base: u32, to_base: u32) -> Result<Vec<u32>,
     Ok(converted.digits())
 }
 
+/// Parse a string into digits of the given base, most significant first.
+///
+/// Leading zeros are kept, the empty string gives no digits.
+///
+/// Example:
+///   parse("ff", 16, &Alphabet::BASE36) == Ok(vec![15, 15])
+pub fn parse(s: &str, base: u32, alphabet: &Alphabet) -> Result<Vec<u32>, Error> {
+    if base < 2 || base > alphabet.len() {
+        return Err(Error::InvalidInputBase);
+    }
+
+    let mut digits = Vec::with_capacity(s.len());
+    for (position, character) in s.char_indices() {
+        if alphabet.is_ignored(character) {
+            continue;
+        }
+        let digit = alphabet.digit(character).ok_or(Error::InvalidCharacter {
+            character,
+            position,
+        })?;
+        if digit >= base {
+            return Err(Error::InvalidDigit { digit, position });
+        }
+        digits.push(digit);
+    }
+    Ok(digits)
+}
+
+/// Format digits, most significant first, with symbols of the alphabet.
+///
+/// Example:
+///   format(&[15, 15], &Alphabet::BASE36) == Ok("ff".to_string())
+pub fn format(digits: &[u32], alphabet: &Alphabet) -> Result<String, Error> {
+    digits
+        .iter()
+        .enumerate()
+        .map(|(position, &digit)| {
+            alphabet
+                .symbol(digit)
+                .ok_or(Error::InvalidDigit { digit, position })
+        })
+        .collect()
+}
+
+/// Convert a mixed-radix number to digits of an ordinary base.
+///
+/// `radices` are the radices of every position but the most significant
+/// one, which is not bounded, f.e. `[24, 60, 60]` for days, hours, minutes
+/// and seconds. Missing leading digits are zeros, so `[5, 30]` is 5 minutes
+/// and 30 seconds. More than `radices.len() + 1` digits is
+/// `Error::TooManyDigits`.
+///
+/// Example:
+///   from_mixed_radix(&[1, 2, 0, 5], &[24, 60, 60], 10) == Ok(vec![9, 3, 6, 0, 5])
+pub fn from_mixed_radix(digits: &[u32], radices: &[u32], to_base: u32) -> Result<Vec<u32>, Error> {
+    if radices.iter().any(|&r| r < 2) {
+        return Err(Error::InvalidInputBase);
+    }
+    if digits.len() > radices.len() + 1 {
+        return Err(Error::TooManyDigits {
+            count: digits.len(),
+            max: radices.len() + 1,
+        });
+    }
+
+    // position of the first digit when missing leading digits are added
+    let offset = radices.len() + 1 - digits.len();
+
+    let mut num = Number::new(to_base).map_err(|_| Error::InvalidOutputBase)?;
+    for (position, &digit) in digits.iter().enumerate() {
+        if let Some(&radix) = (position + offset).checked_sub(1).map(|i| &radices[i]) {
+            if digit >= radix {
+                return Err(Error::InvalidDigit { digit, position });
+            }
+            num *= radix;
+        }
+        num += &Number::from_u32(to_base, digit)?;
+    }
+
+    Ok(num.digits())
+}
+
+/// Convert digits of an ordinary base to a mixed-radix number, see
+/// `from_mixed_radix`. The result has a digit for every radix and the
+/// unbounded most significant digit, or `Error::Overflow` if that digit
+/// does not fit into `u32`.
+///
+/// Example:
+///   to_mixed_radix(&[9, 3, 6, 0, 5], 10, &[24, 60, 60]) == Ok(vec![1, 2, 0, 5])
+pub fn to_mixed_radix(digits: &[u32], from_base: u32, radices: &[u32]) -> Result<Vec<u32>, Error> {
+    if radices.iter().any(|&r| r < 2) {
+        return Err(Error::InvalidOutputBase);
+    }
+
+    let mut num = Number::from_digits(from_base, digits)?;
+    let mut mixed = Vec::with_capacity(radices.len() + 1);
+    for &radix in radices.iter().rev() {
+        let (quotient, remainder) = num.div_rem(&Number::from_u32(from_base, radix)?);
+        mixed.push(to_u32(&remainder).expect("remainder is less than the radix"));
+        num = quotient;
+    }
+    mixed.push(to_u32(&num).ok_or(Error::Overflow)?);
+    mixed.reverse();
+
+    Ok(mixed)
+}
+
+fn to_u32(num: &Number) -> Option<u32> {
+    num.digits().iter().try_fold(0u32, |acc, &digit| {
+        acc.checked_mul(num.base())?.checked_add(digit)
+    })
+}
+
+/// Convert a number in bijective base-k to digits of an ordinary base.
+///
+/// Bijective digits are `1..=k` and there is no zero digit, the empty
+/// slice is zero. F.e. spreadsheet columns `A`, `Z`, `AA` are `[1]`,
+/// `[26]`, `[1, 1]` in bijective base-26.
+pub fn from_bijective(digits: &[u32], k: u32, to_base: u32) -> Result<Vec<u32>, Error> {
+    if k < 2 {
+        return Err(Error::InvalidInputBase);
+    }
+
+    if let Some((position, &digit)) = digits.iter().enumerate().find(|(_, &x)| x == 0 || x > k) {
+        return Err(Error::InvalidDigit { digit, position });
+    }
+
+    // digit k is zero with a carry to the next position,
+    // digit and carry may not fit into u32 when k is u32::MAX
+    let mut standard = Vec::with_capacity(digits.len() + 1);
+    let mut carry = 0;
+    for &digit in digits.iter().rev() {
+        let x = u64::from(digit) + u64::from(carry);
+        carry = if x >= u64::from(k) { 1 } else { 0 };
+        standard.push((x - u64::from(carry * k)) as u32);
+    }
+    standard.push(carry);
+    standard.reverse();
+
+    convert(&standard, k, to_base)
+}
+
+/// Convert digits of an ordinary base to bijective base-k, see
+/// `from_bijective`. Zero is the empty vector.
+pub fn to_bijective(digits: &[u32], from_base: u32, k: u32) -> Result<Vec<u32>, Error> {
+    let mut standard = convert(digits, from_base, k)?;
+    if standard == [0] {
+        return Ok(Vec::new());
+    }
+
+    // zero digit becomes k borrowed from the next position
+    let mut bijective = Vec::with_capacity(standard.len());
+    let mut borrow = 0;
+    let top = standard.remove(0);
+    for digit in standard.into_iter().rev() {
+        let (x, b) = match digit.checked_sub(borrow) {
+            // digit is at most borrow, so this is at most k
+            Some(0) | None => (k - borrow + digit, 1),
+            Some(x) => (x, 0),
+        };
+        bijective.push(x);
+        borrow = b;
+    }
+    if top > borrow {
+        bijective.push(top - borrow);
+    }
+    bijective.reverse();
+
+    Ok(bijective)
+}
+
+/// Convert a stream of digits, least significant first, to a stream of
+/// digits in another base, least significant first, in constant memory.
+///
+/// Both bases must be powers of the same number, f.e. 16 and 8, 2 and 256
+/// or 10 and 1000, otherwise `Error::IncompatibleBases` is returned.
+/// Like `convert`, the output has no leading zeros and zero is `[0]`.
+/// An invalid digit ends the stream with an error, its position counts
+/// from the least significant digit.
+///
+/// Example:
+///   convert_stream(vec![15, 1], 16, 8)?.collect::<Result<Vec<_>, _>>() == Ok(vec![7, 3])
+pub fn convert_stream<I>(
+    digits: I,
+    from_base: u32,
+    to_base: u32,
+) -> Result<Stream<I::IntoIter>, Error>
+where
+    I: IntoIterator<Item = u32>,
+{
+    if from_base < 2 {
+        return Err(Error::InvalidInputBase);
+    }
+    if to_base < 2 {
+        return Err(Error::InvalidOutputBase);
+    }
+
+    let (from_root, _) = base_number::perfect_power(from_base);
+    let (to_root, _) = base_number::perfect_power(to_base);
+    if from_root != to_root {
+        return Err(Error::IncompatibleBases);
+    }
+
+    Ok(Stream::new(digits.into_iter(), from_base, to_base))
+}
+
+pub mod stream {
+    use super::Error;
+    use std::iter::Fuse;
+
+    /// Iterator returned by `convert_stream`.
+    #[derive(Clone, Debug)]
+    pub struct Stream<I> {
+        // input is polled again after its end when flushing the last digits
+        digits: Fuse<I>,
+        from_base: u64,
+        to_base: u64,
+
+        // index of the next input digit
+        position: usize,
+
+        // input not yet written out, `value < weight`;
+        // weight is a power of the common root and less than
+        // from_base * to_base, so it never overflows
+        value: u64,
+        weight: u64,
+
+        // output zeros which are written only if a non-zero digit follows
+        zeros: usize,
+        pending: Option<u32>,
+
+        written: bool,
+        done: bool,
+    }
+
+    impl<I: Iterator<Item = u32>> Stream<I> {
+        pub(crate) fn new(digits: I, from_base: u32, to_base: u32) -> Self {
+            Stream {
+                digits: digits.fuse(),
+                from_base: from_base.into(),
+                to_base: to_base.into(),
+                position: 0,
+                value: 0,
+                weight: 1,
+                zeros: 0,
+                pending: None,
+                written: false,
+                done: false,
+            }
+        }
+
+        /// Next output digit, including leading zeros.
+        fn next_digit(&mut self) -> Option<Result<u32, Error>> {
+            while self.weight < self.to_base {
+                match self.digits.next() {
+                    Some(digit) if u64::from(digit) >= self.from_base => {
+                        return Some(Err(Error::InvalidDigit {
+                            digit,
+                            position: self.position,
+                        }));
+                    }
+                    Some(digit) => {
+                        self.value += u64::from(digit) * self.weight;
+                        self.weight *= self.from_base;
+                        self.position += 1;
+                    }
+                    None if self.weight > 1 => {
+                        self.weight = self.to_base;
+                    }
+                    None => return None,
+                }
+            }
+
+            let digit = self.value % self.to_base;
+            self.value /= self.to_base;
+            self.weight /= self.to_base;
+            Some(Ok(digit as u32))
+        }
+    }
+
+    impl<I: Iterator<Item = u32>> Iterator for Stream<I> {
+        type Item = Result<u32, Error>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.done {
+                return None;
+            }
+
+            while self.pending.is_none() {
+                match self.next_digit() {
+                    Some(Ok(0)) => self.zeros += 1,
+                    Some(Ok(digit)) => self.pending = Some(digit),
+                    Some(Err(err)) => {
+                        self.done = true;
+                        return Some(Err(err));
+                    }
+                    None => {
+                        self.done = true;
+                        return if self.written { None } else { Some(Ok(0)) };
+                    }
+                }
+            }
+
+            self.written = true;
+            if self.zeros > 0 {
+                self.zeros -= 1;
+                Some(Ok(0))
+            } else {
+                self.pending.take().map(Ok)
+            }
+        }
+    }
+}
+
+pub mod real {
+    use super::{Alphabet, Error};
+
+    /// Number with a sign and a radix point, f.e. `-101.011` in binary.
+    ///
+    /// Digits are most significant first. A digit position reported in
+    /// errors counts integer digits and then fraction digits, as if the
+    /// radix point were not there.
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct Real {
+        pub negative: bool,
+        pub integer: Vec<u32>,
+        pub fraction: Vec<u32>,
+    }
+
+    /// Result of `Real::convert`.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Converted {
+        pub number: Real,
+
+        /// Fraction does not terminate within the requested number of
+        /// digits, the remaining digits were dropped.
+        pub truncated: bool,
+    }
+
+    impl Real {
+        /// Parse an optional sign, integer digits, and an optional radix
+        /// point followed by fraction digits, f.e. `-ff.8` in hexadecimal.
+        pub fn parse(s: &str, base: u32, alphabet: &Alphabet) -> Result<Self, Error> {
+            let (negative, unsigned) = match s.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, s.strip_prefix('+').unwrap_or(s)),
+            };
+            let sign_len = s.len() - unsigned.len();
+
+            let (integer, fraction) = match unsigned.find('.') {
+                Some(point) => (&unsigned[..point], &unsigned[point + 1..]),
+                None => (unsigned, ""),
+            };
+
+            // byte offsets are reported against the whole string
+            let shift = |offset: usize| {
+                move |err| match err {
+                    Error::InvalidDigit { digit, position } => Error::InvalidDigit {
+                        digit,
+                        position: position + offset,
+                    },
+                    Error::InvalidCharacter {
+                        character,
+                        position,
+                    } => Error::InvalidCharacter {
+                        character,
+                        position: position + offset,
+                    },
+                    err => err,
+                }
+            };
+
+            Ok(Real {
+                negative,
+                integer: super::parse(integer, base, alphabet).map_err(shift(sign_len))?,
+                fraction: super::parse(fraction, base, alphabet)
+                    .map_err(shift(sign_len + integer.len() + 1))?,
+            })
+        }
+
+        /// Format with a leading `-` if negative, the radix point is
+        /// omitted when there are no fraction digits.
+        pub fn format(&self, alphabet: &Alphabet) -> Result<String, Error> {
+            let mut s = String::new();
+            if self.negative {
+                s.push('-');
+            }
+            if self.integer.is_empty() {
+                s.push_str(&super::format(&[0], alphabet)?);
+            } else {
+                s.push_str(&super::format(&self.integer, alphabet)?);
+            }
+            if !self.fraction.is_empty() {
+                s.push('.');
+                s.push_str(
+                    &super::format(&self.fraction, alphabet).map_err(|err| match err {
+                        Error::InvalidDigit { digit, position } => Error::InvalidDigit {
+                            digit,
+                            position: position + self.integer.len(),
+                        },
+                        err => err,
+                    })?,
+                );
+            }
+            Ok(s)
+        }
+
+        /// Convert between bases, producing at most `max_fraction_digits`
+        /// digits after the radix point.
+        ///
+        /// Fraction is truncated, not rounded. Leading zeros of the integer
+        /// part and trailing zeros of the fraction are dropped, zero is
+        /// never negative.
+        pub fn convert(
+            &self,
+            from_base: u32,
+            to_base: u32,
+            max_fraction_digits: usize,
+        ) -> Result<Converted, Error> {
+            let integer = super::convert(&self.integer, from_base, to_base)?;
+
+            if let Some((i, &digit)) = self
+                .fraction
+                .iter()
+                .enumerate()
+                .find(|(_, &x)| x >= from_base)
+            {
+                return Err(Error::InvalidDigit {
+                    digit,
+                    position: self.integer.len() + i,
+                });
+            }
+
+            // fraction is remainder / from_base^len, every step multiplies
+            // it by to_base and takes the integer part as the next digit
+            let mut remainder = self.fraction.clone();
+            let mut fraction = Vec::new();
+            loop {
+                while remainder.last() == Some(&0) {
+                    remainder.pop();
+                }
+                if remainder.is_empty() || fraction.len() == max_fraction_digits {
+                    break;
+                }
+
+                let mut carry = 0u64;
+                for digit in remainder.iter_mut().rev() {
+                    let x = u64::from(*digit) * u64::from(to_base) + carry;
+                    *digit = (x % u64::from(from_base)) as u32;
+                    carry = x / u64::from(from_base);
+                }
+                fraction.push(carry as u32);
+            }
+
+            while fraction.last() == Some(&0) {
+                fraction.pop();
+            }
+
+            let is_zero = integer == [0] && fraction.is_empty();
+            Ok(Converted {
+                number: Real {
+                    negative: self.negative && !is_zero,
+                    integer,
+                    fraction,
+                },
+                truncated: !remainder.is_empty(),
+            })
+        }
+    }
+}
+
+pub mod alphabet {
+    use super::Error;
+
+    /// Symbols used to write digits: the n-th symbol is the digit n.
+    ///
+    /// Any base up to the number of symbols can be written with an alphabet,
+    /// f.e. hexadecimal numbers with `Alphabet::BASE36`.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Alphabet<'a> {
+        symbols: &'a str,
+
+        // letters are parsed regardless of their case
+        case_insensitive: bool,
+
+        // additional symbols accepted when parsing: (alias, symbol)
+        aliases: &'a [(char, char)],
+
+        // symbols skipped when parsing
+        ignored: &'a [char],
+    }
+
+    impl Alphabet<'static> {
+        /// Digits and lowercase letters, parsed case-insensitively.
+        /// Covers the usual notation for bases 2 to 36.
+        pub const BASE36: Self = Alphabet {
+            symbols: "0123456789abcdefghijklmnopqrstuvwxyz",
+            case_insensitive: true,
+            aliases: &[],
+            ignored: &[],
+        };
+
+        /// Bitcoin base58: no `0`, `O`, `I` and `l`.
+        pub const BASE58: Self = Alphabet {
+            symbols: "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz",
+            case_insensitive: false,
+            aliases: &[],
+            ignored: &[],
+        };
+
+        /// Digits, uppercase and lowercase letters.
+        pub const BASE62: Self = Alphabet {
+            symbols: "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz",
+            case_insensitive: false,
+            aliases: &[],
+            ignored: &[],
+        };
+
+        /// Crockford's base32: parsing is case-insensitive, `O` is read
+        /// as `0`, `I` and `L` as `1`, hyphens are ignored.
+        pub const CROCKFORD32: Self = Alphabet {
+            symbols: "0123456789ABCDEFGHJKMNPQRSTVWXYZ",
+            case_insensitive: true,
+            aliases: &[('O', '0'), ('I', '1'), ('L', '1')],
+            ignored: &['-'],
+        };
+    }
+
+    impl<'a> Alphabet<'a> {
+        /// Case-sensitive alphabet of the given symbols, at least two
+        /// and all different, otherwise `Error::InvalidAlphabet`.
+        pub fn new(symbols: &'a str) -> Result<Self, Error> {
+            let repeated = symbols
+                .char_indices()
+                .any(|(i, c)| symbols[i + c.len_utf8()..].contains(c));
+            if repeated || symbols.chars().nth(1).is_none() {
+                return Err(Error::InvalidAlphabet);
+            }
+
+            Ok(Alphabet {
+                symbols,
+                case_insensitive: false,
+                aliases: &[],
+                ignored: &[],
+            })
+        }
+
+        /// Number of symbols, which is the largest base of the alphabet.
+        pub fn len(&self) -> u32 {
+            self.symbols.chars().count() as u32
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.symbols.is_empty()
+        }
+
+        pub fn symbol(&self, digit: u32) -> Option<char> {
+            self.symbols.chars().nth(digit as usize)
+        }
+
+        pub fn digit(&self, symbol: char) -> Option<u32> {
+            let symbol = self
+                .aliases
+                .iter()
+                .find(|(alias, _)| self.matches(*alias, symbol))
+                .map_or(symbol, |&(_, s)| s);
+
+            self.symbols
+                .chars()
+                .position(|s| self.matches(s, symbol))
+                .map(|digit| digit as u32)
+        }
+
+        pub(crate) fn is_ignored(&self, symbol: char) -> bool {
+            self.ignored.contains(&symbol)
+        }
+
+        fn matches(&self, a: char, b: char) -> bool {
+            a == b || self.case_insensitive && a.eq_ignore_ascii_case(&b)
+        }
+    }
+}
+
 mod base_number {
+    use super::{Alphabet, Error};
+    use std::borrow::Cow;
+    use std::cmp::Ordering;
+    use std::fmt;
+    use std::str::FromStr;
 
+    /// Arbitrary precision signed integer stored as digits in any base.
+    ///
+    /// Operands of arithmetic may have different bases, the result has
+    /// the base of the left operand. Division truncates towards zero,
+    /// remainder has the sign of the dividend, like for primitive integers.
     #[derive(Clone, Debug)]
     pub struct Number {
         base: u32,
 
-        // little-endian.
+        // sign of the number, zero is never negative
+        negative: bool,
+
+        // magnitude, little-endian.
         // F.e. 1234 will be stored as vec![4,3,2,1]
         digits: Vec<u32>,
     }
@@ -57,23 +682,31 @@ mod base_number {
         type Output = Self;
 
         fn add(mut self, other: &'_ Number) -> Number {
-            if self.base == other.base {
-                self.add_same_base(&other);
-            } else {
-                self.add_same_base(&other.convert(self.base).unwrap());
-            }
-
+            self += other;
             self
         }
     }
 
     impl std::ops::AddAssign<&'_ Self> for Number {
         fn add_assign(&mut self, rhs: &'_ Self) {
-            if self.base == rhs.base {
-                self.add_same_base(&rhs);
-            } else {
-                self.add_same_base(&rhs.convert(self.base).unwrap());
-            }
+            let rhs = rhs.in_base(self.base);
+            self.add_signed(rhs.negative, &rhs.digits);
+        }
+    }
+
+    impl std::ops::Sub<&'_ Self> for Number {
+        type Output = Self;
+
+        fn sub(mut self, other: &'_ Number) -> Number {
+            self -= other;
+            self
+        }
+    }
+
+    impl std::ops::SubAssign<&'_ Self> for Number {
+        fn sub_assign(&mut self, rhs: &'_ Self) {
+            let rhs = rhs.in_base(self.base);
+            self.add_signed(!rhs.negative, &rhs.digits);
         }
     }
 
@@ -94,14 +727,125 @@ mod base_number {
 
     impl std::ops::MulAssign<&'_ Self> for Number {
         fn mul_assign(&mut self, rhs: &'_ Self) {
-            if self.base == rhs.base {
-                self.multiply_same_base(&rhs);
-            } else {
-                self.multiply_same_base(&rhs.convert(self.base).unwrap());
+            self.multiply_same_base(&rhs.in_base(self.base));
+        }
+    }
+
+    impl std::ops::Mul<&'_ Self> for Number {
+        type Output = Self;
+
+        fn mul(mut self, rhs: &'_ Self) -> Self {
+            self *= rhs;
+            self
+        }
+    }
+
+    /// Panics if the divisor is zero.
+    impl std::ops::Div<&'_ Self> for Number {
+        type Output = Self;
+
+        fn div(self, rhs: &'_ Self) -> Self {
+            self.div_rem(rhs).0
+        }
+    }
+
+    /// Panics if the divisor is zero.
+    impl std::ops::Rem<&'_ Self> for Number {
+        type Output = Self;
+
+        fn rem(self, rhs: &'_ Self) -> Self {
+            self.div_rem(rhs).1
+        }
+    }
+
+    impl std::ops::Neg for Number {
+        type Output = Self;
+
+        fn neg(mut self) -> Self {
+            self.negative = !self.negative && !self.is_zero();
+            self
+        }
+    }
+
+    /// Numbers are equal if their values are, whatever their bases.
+    impl PartialEq for Number {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(other) == Ordering::Equal
+        }
+    }
+
+    impl Eq for Number {}
+
+    impl PartialOrd for Number {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Number {
+        fn cmp(&self, other: &Self) -> Ordering {
+            let other = other.in_base(self.base);
+            match (self.negative, other.negative) {
+                (false, true) => Ordering::Greater,
+                (true, false) => Ordering::Less,
+                (false, false) => cmp_magnitude(&self.digits, &other.digits),
+                (true, true) => cmp_magnitude(&other.digits, &self.digits),
             }
         }
     }
 
+    /// Decimal digits with a leading `-` if negative, whatever the base,
+    /// like primitive integers.
+    impl fmt::Display for Number {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let decimal = self.in_base(10);
+            let digits: String = decimal
+                .digits
+                .iter()
+                .rev()
+                .map(|&d| std::char::from_digit(d, 10).unwrap())
+                .collect();
+            f.pad_integral(!self.negative, "", &digits)
+        }
+    }
+
+    /// Decimal number with an optional sign, f.e. `-1234`.
+    /// The result is in base 10.
+    impl FromStr for Number {
+        type Err = Error;
+
+        fn from_str(s: &str) -> Result<Self, Error> {
+            let (negative, unsigned) = match s.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, s.strip_prefix('+').unwrap_or(s)),
+            };
+            let sign_len = s.len() - unsigned.len();
+
+            // byte offsets are reported against the whole string
+            let digits =
+                super::parse(unsigned, 10, &Alphabet::BASE36).map_err(|err| match err {
+                    Error::InvalidDigit { digit, position } => Error::InvalidDigit {
+                        digit,
+                        position: position + sign_len,
+                    },
+                    Error::InvalidCharacter {
+                        character,
+                        position,
+                    } => Error::InvalidCharacter {
+                        character,
+                        position: position + sign_len,
+                    },
+                    err => err,
+                })?;
+            if digits.is_empty() {
+                return Err(Error::NoDigits);
+            }
+
+            let n = Number::from_digits(10, &digits)?;
+            Ok(if negative { -n } else { n })
+        }
+    }
+
     impl Number {
         pub fn new(base: u32) -> Result<Self, super::Error> {
             if base < 2 {
@@ -110,34 +854,40 @@ mod base_number {
 
             Ok(Number {
                 base,
+                negative: false,
                 digits: vec![0],
             })
         }
 
         pub fn from_u32(base: u32, number: u32) -> Result<Self, super::Error> {
-            if base < 2 {
-                return Err(super::Error::InvalidInputBase);
-            };
+            Number::from_u64(base, number.into())
+        }
 
-            Ok(Number {
-                base,
-                digits: Number::to_digits(number, base),
-            })
+        pub fn from_u64(base: u32, number: u64) -> Result<Self, super::Error> {
+            let mut n = Number::new(base)?;
+            n.digits = Number::to_digits(number, base);
+            n.drop_zeros();
+            Ok(n)
+        }
+
+        pub fn from_i64(base: u32, number: i64) -> Result<Self, super::Error> {
+            let n = Number::from_u64(base, number.unsigned_abs())?;
+            Ok(if number < 0 { -n } else { n })
         }
 
+        /// Non-negative number from its digits, most significant first.
         pub fn from_digits(base: u32, digits: &[u32]) -> Result<Self, super::Error> {
             if base < 2 {
                 return Err(super::Error::InvalidInputBase);
             };
 
-            for &x in digits {
-                if x >= base {
-                    return Err(super::Error::InvalidDigit(x));
-                }
+            if let Some((position, &digit)) = digits.iter().enumerate().find(|(_, &x)| x >= base) {
+                return Err(super::Error::InvalidDigit { digit, position });
             }
 
             let mut n = Number {
                 base,
+                negative: false,
                 digits: digits.iter().rev().copied().collect(),
             };
 
@@ -155,118 +905,158 @@ mod base_number {
                 return Ok(self.clone());
             }
 
-            let mut result = Number::new(to_base).unwrap();
-
-            // self.base as Number with base = to_base
-            let base_in_dest_base = Number::from_u32(to_base, self.base).unwrap();
-
-            // powers of self.base (as Number with base = to_base), starting from 0:
-            // 1, base, base^2, base^3,...
-            let base_powers = (0..).scan(Number::from_u32(to_base, 1).unwrap(), |pow, _| {
-                let res = pow.clone();
-                *pow *= &base_in_dest_base;
-                Some(res)
-            });
-
-            for (&digit, base_power) in self.digits.iter().zip(base_powers) {
-                let digit_in_dest_base = base_power * digit;
-                result += &digit_in_dest_base;
-            }
+            let digits = match (perfect_power(self.base), perfect_power(to_base)) {
+                ((root, from_exp), (to_root, to_exp)) if root == to_root => {
+                    regroup(&self.digits, root, from_exp, to_exp)
+                }
+                _ => convert_magnitude(&self.digits, self.base, to_base),
+            };
 
+            let mut result = Number {
+                base: to_base,
+                negative: self.negative,
+                digits,
+            };
+            result.drop_zeros();
             Ok(result)
         }
 
+        pub fn base(&self) -> u32 {
+            self.base
+        }
+
+        /// Digits of the absolute value, most significant first.
         pub fn digits(&self) -> Vec<u32> {
             let mut digits = self.digits.clone();
             digits.reverse();
             digits
         }
 
-        fn add_same_base(&mut self, other: &Self) {
-            assert!(self.base == other.base);
-            self.raw_add_same_base(other);
-            self.normalize();
-        }
-
-        fn multiply_by_power_of_base(&mut self, power: usize) {
-            self.digits.resize(self.digits.len() + power, 0);
-            self.digits.rotate_right(power);
+        pub fn is_zero(&self) -> bool {
+            self.digits == [0]
         }
 
-        fn multiply_by_u32(&mut self, multiplier: u32) {
-            self.raw_multiply_by_u32(multiplier);
-            self.normalize();
+        pub fn is_negative(&self) -> bool {
+            self.negative
         }
 
-        fn multiply_same_base(&mut self, other: &Self) {
-            assert!(self.base == other.base);
-            let mut result = self.raw_multiply_same_base(other);
-            result.normalize();
-            self.digits = result.digits;
-        }
-
-        /// truncate zeroes from the end (high digits)
-        fn drop_zeros(&mut self) {
-            if let Some(last_non_zero_position) = self.digits.iter().rposition(|&x| x > 0) {
-                self.digits.resize(last_non_zero_position + 1, 0)
+        /// -1, 0 or 1 depending on the sign of the number.
+        pub fn signum(&self) -> i32 {
+            if self.negative {
+                -1
+            } else if self.is_zero() {
+                0
             } else {
-                // all zeroes
-                self.digits = vec![0];
-                return;
+                1
             }
         }
 
-        /// correct digits which are >= base
-        fn normalize(&mut self) {
-            self.drop_zeros();
+        pub fn abs(mut self) -> Self {
+            self.negative = false;
+            self
+        }
 
-            let mut shift = 0;
-            for digit in &mut self.digits {
-                let sum = shift + *digit;
-                shift = sum / self.base;
-                *digit = sum % self.base;
+        pub fn pow(&self, mut exp: u32) -> Self {
+            let mut result = Number::from_u32(self.base, 1).unwrap();
+            let mut square = self.clone();
+            while exp > 0 {
+                if exp & 1 == 1 {
+                    result *= &square;
+                }
+                exp >>= 1;
+                if exp > 0 {
+                    let copy = square.clone();
+                    square *= &copy;
+                }
             }
+            result
+        }
 
-            if shift > 0 {
-                Number::to_digits(shift, self.base).iter().for_each(|&d| {
-                    self.digits.push(d);
-                })
-            }
+        /// Quotient and remainder, panics if the divisor is zero.
+        pub fn div_rem(&self, other: &Self) -> (Self, Self) {
+            self.checked_div_rem(other)
+                .expect("attempt to divide by zero")
         }
 
-        fn raw_multiply_by_u32(&mut self, multiplier: u32) {
-            for digit in &mut self.digits {
-                *digit *= multiplier;
+        /// Quotient and remainder, `None` if the divisor is zero.
+        pub fn checked_div_rem(&self, other: &Self) -> Option<(Self, Self)> {
+            let other = other.in_base(self.base);
+            if other.is_zero() {
+                return None;
             }
-        }
 
-        fn raw_multiply_same_base(&self, other: &Self) -> Number {
-            let mut result = Number {
+            let (quotient, remainder) = divide_magnitude(&self.digits, &other.digits, self.base);
+            let mut quotient = Number {
+                base: self.base,
+                negative: self.negative != other.negative,
+                digits: quotient,
+            };
+            let mut remainder = Number {
                 base: self.base,
-                digits: Vec::new(),
+                negative: self.negative,
+                digits: remainder,
             };
+            quotient.drop_zeros();
+            remainder.drop_zeros();
+            Some((quotient, remainder))
+        }
 
-            for (idx, &other_digit) in other.digits.iter().enumerate() {
-                let mut copy = self.clone();
-                copy.raw_multiply_by_u32(other_digit);
-                copy.multiply_by_power_of_base(idx);
-                result.raw_add_same_base(&copy);
+        // other number in the base of this one
+        fn in_base(&self, base: u32) -> Cow<'_, Self> {
+            if self.base == base {
+                Cow::Borrowed(self)
+            } else {
+                Cow::Owned(self.convert(base).unwrap())
             }
+        }
 
-            result
+        // add number with the given sign and magnitude in the same base
+        fn add_signed(&mut self, negative: bool, digits: &[u32]) {
+            if self.negative == negative {
+                add_magnitude(&mut self.digits, digits, self.base);
+            } else if cmp_magnitude(&self.digits, digits) != Ordering::Less {
+                sub_magnitude(&mut self.digits, digits, self.base);
+            } else {
+                let mut result = digits.to_vec();
+                sub_magnitude(&mut result, &self.digits, self.base);
+                self.digits = result;
+                self.negative = negative;
+            }
+            self.drop_zeros();
         }
 
-        fn raw_add_same_base(&mut self, other: &Self) {
-            if other.digits.len() > self.digits.len() {
-                self.digits.resize(other.digits.len(), 0);
+        fn multiply_by_u32(&mut self, multiplier: u32) {
+            let base = u128::from(self.base);
+            let mut carry = 0u128;
+            for digit in &mut self.digits {
+                let product = u128::from(*digit) * u128::from(multiplier) + carry;
+                *digit = (product % base) as u32;
+                carry = product / base;
+            }
+            while carry > 0 {
+                self.digits.push((carry % base) as u32);
+                carry /= base;
             }
+            self.drop_zeros();
+        }
+
+        fn multiply_same_base(&mut self, other: &Self) {
+            assert!(self.base == other.base);
+            self.digits = multiply_magnitude(&self.digits, &other.digits, self.base);
+            self.negative = self.negative != other.negative;
+            self.drop_zeros();
+        }
 
-            for (idx, &other_digit) in other.digits.iter().enumerate() {
-                self.digits[idx] += other_digit;
+        /// truncate zeroes from the end (high digits)
+        fn drop_zeros(&mut self) {
+            trim(&mut self.digits);
+            if self.is_zero() {
+                self.negative = false;
             }
         }
 
-        fn to_digits(number: u32, base: u32) -> Vec<u32> {
+        fn to_digits(number: u64, base: u32) -> Vec<u32> {
+            let base = u64::from(base);
             (0..)
                 .scan(number, |number, _| {
                     if *number == 0 {
@@ -274,10 +1064,318 @@ mod base_number {
                     } else {
                         let digit = *number % base;
                         *number /= base;
-                        Some(digit)
+                        Some(digit as u32)
                     }
                 })
                 .collect()
         }
     }
+
+    // Helpers below work on little-endian magnitudes without leading zeros
+    // (except for zero itself, which is [0]).
+
+    fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+        a.len()
+            .cmp(&b.len())
+            .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+    }
+
+    fn add_magnitude(a: &mut Vec<u32>, b: &[u32], base: u32) {
+        add_magnitude_at(a, b, 0, base);
+    }
+
+    // a += b * base^shift
+    fn add_magnitude_at(a: &mut Vec<u32>, b: &[u32], shift: usize, base: u32) {
+        if b.len() + shift > a.len() {
+            a.resize(b.len() + shift, 0);
+        }
+
+        let base = u64::from(base);
+        let mut carry = 0;
+        for (idx, digit) in a[shift..].iter_mut().enumerate() {
+            if idx >= b.len() && carry == 0 {
+                break;
+            }
+            let sum = u64::from(*digit) + u64::from(b.get(idx).copied().unwrap_or(0)) + carry;
+            *digit = (sum % base) as u32;
+            carry = sum / base;
+        }
+        if carry > 0 {
+            a.push(carry as u32);
+        }
+    }
+
+    // a -= b, a must not be less than b
+    fn sub_magnitude(a: &mut [u32], b: &[u32], base: u32) {
+        let mut borrow = 0;
+        for (idx, digit) in a.iter_mut().enumerate() {
+            if idx >= b.len() && borrow == 0 {
+                break;
+            }
+            let subtrahend = i64::from(b.get(idx).copied().unwrap_or(0)) + borrow;
+            let mut diff = i64::from(*digit) - subtrahend;
+            if diff < 0 {
+                diff += i64::from(base);
+                borrow = 1;
+            } else {
+                borrow = 0;
+            }
+            *digit = diff as u32;
+        }
+        debug_assert!(borrow == 0);
+    }
+
+    // below this number of digits schoolbook multiplication is faster
+    const KARATSUBA_THRESHOLD: usize = 32;
+
+    fn multiply_magnitude(a: &[u32], b: &[u32], base: u32) -> Vec<u32> {
+        if a.len().min(b.len()) < KARATSUBA_THRESHOLD {
+            return multiply_schoolbook(a, b, base);
+        }
+
+        // a = a1 * base^m + a0, b = b1 * base^m + b0
+        let m = a.len().max(b.len()) / 2;
+        let (a0, a1) = a.split_at(m.min(a.len()));
+        let (b0, b1) = b.split_at(m.min(b.len()));
+
+        let z0 = multiply_magnitude(a0, b0, base);
+        let z2 = multiply_magnitude(a1, b1, base);
+
+        // z1 = (a0 + a1)(b0 + b1) - z0 - z2 = a0 * b1 + a1 * b0
+        let mut a_sum = a0.to_vec();
+        add_magnitude(&mut a_sum, a1, base);
+        let mut b_sum = b0.to_vec();
+        add_magnitude(&mut b_sum, b1, base);
+        let mut z1 = multiply_magnitude(&a_sum, &b_sum, base);
+        sub_magnitude(&mut z1, &z0, base);
+        sub_magnitude(&mut z1, &z2, base);
+
+        let mut result = z0;
+        add_magnitude_at(&mut result, &z1, m, base);
+        add_magnitude_at(&mut result, &z2, 2 * m, base);
+        result
+    }
+
+    // digits and carries are less than the base, so sums of their
+    // products with a digit are less than base^2 and fit into u64
+    fn multiply_schoolbook(a: &[u32], b: &[u32], base: u32) -> Vec<u32> {
+        let base = u64::from(base);
+        let mut result = vec![0u32; a.len() + b.len()];
+        for (i, &x) in a.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &y) in b.iter().enumerate() {
+                let t = u64::from(result[i + j]) + u64::from(x) * u64::from(y) + carry;
+                result[i + j] = (t % base) as u32;
+                carry = t / base;
+            }
+            let mut k = i + b.len();
+            while carry > 0 {
+                let t = u64::from(result[k]) + carry;
+                result[k] = (t % base) as u32;
+                carry = t / base;
+                k += 1;
+            }
+        }
+        result
+    }
+
+    // below this number of digits conversion is done directly
+    const DIRECT_CONVERSION_LIMIT: usize = 32;
+
+    /// Conversion of word-sized limbs: digits are packed into limbs in
+    /// the largest power of the source base which fits into `u32`, those
+    /// are converted to limbs in such a power of the target base, which
+    /// are split back into digits. Arithmetic on limbs does the work of
+    /// many digits at once.
+    fn convert_magnitude(digits: &[u32], from_base: u32, to_base: u32) -> Vec<u32> {
+        let (from_limb, from_len) = limb_base(from_base);
+        let (to_limb, to_len) = limb_base(to_base);
+
+        let limbs: Vec<u32> = digits
+            .chunks(from_len)
+            .map(|chunk| chunk.iter().rev().fold(0, |limb, &d| limb * from_base + d))
+            .collect();
+
+        let mut result: Vec<u32> = convert_limbs(&limbs, from_limb, to_limb)
+            .into_iter()
+            .flat_map(|limb| {
+                (0..to_len).scan(limb, move |rest, _| {
+                    let d = *rest % to_base;
+                    *rest /= to_base;
+                    Some(d)
+                })
+            })
+            .collect();
+        trim(&mut result);
+        result
+    }
+
+    /// Largest power of the base which fits into `u32` and its exponent.
+    fn limb_base(base: u32) -> (u32, usize) {
+        let (mut limb, mut len) = (base, 1);
+        while let Some(next) = limb.checked_mul(base) {
+            limb = next;
+            len += 1;
+        }
+        (limb, len)
+    }
+
+    /// Divide and conquer conversion: the high half of the digits is
+    /// converted separately from the low half and then multiplied by
+    /// the matching power of the source base, which is precomputed in
+    /// the target base. With Karatsuba multiplication this is subquadratic.
+    fn convert_limbs(digits: &[u32], from_base: u32, to_base: u32) -> Vec<u32> {
+        // powers[k] = from_base^(DIRECT_CONVERSION_LIMIT * 2^k) in to_base
+        let mut power = vec![0; DIRECT_CONVERSION_LIMIT];
+        power.push(1);
+        let mut powers = vec![convert_directly(&power, from_base, to_base)];
+        while DIRECT_CONVERSION_LIMIT << powers.len() < digits.len() {
+            let last = &powers[powers.len() - 1];
+            let mut square = multiply_magnitude(last, last, to_base);
+            trim(&mut square);
+            powers.push(square);
+        }
+
+        convert_recursive(digits, from_base, to_base, &powers)
+    }
+
+    fn convert_recursive(
+        digits: &[u32],
+        from_base: u32,
+        to_base: u32,
+        powers: &[Vec<u32>],
+    ) -> Vec<u32> {
+        if digits.len() <= DIRECT_CONVERSION_LIMIT {
+            return convert_directly(digits, from_base, to_base);
+        }
+
+        // split at the largest precomputed power below the length
+        let k = (0..powers.len())
+            .rev()
+            .find(|&k| DIRECT_CONVERSION_LIMIT << k < digits.len())
+            .unwrap();
+        let (low, high) = digits.split_at(DIRECT_CONVERSION_LIMIT << k);
+
+        let high = convert_recursive(high, from_base, to_base, powers);
+        let mut result = multiply_magnitude(&high, &powers[k], to_base);
+        add_magnitude(
+            &mut result,
+            &convert_recursive(low, from_base, to_base, powers),
+            to_base,
+        );
+        trim(&mut result);
+        result
+    }
+
+    // Horner's method: result = result * from_base + digit,
+    // carries are less than from_base, so that u64 is enough
+    fn convert_directly(digits: &[u32], from_base: u32, to_base: u32) -> Vec<u32> {
+        let to = u64::from(to_base);
+        let mut result = vec![0];
+        for &digit in digits.iter().rev() {
+            let mut carry = u64::from(digit);
+            for d in &mut result {
+                let t = u64::from(*d) * u64::from(from_base) + carry;
+                *d = (t % to) as u32;
+                carry = t / to;
+            }
+            while carry > 0 {
+                result.push((carry % to) as u32);
+                carry /= to;
+            }
+        }
+        trim(&mut result);
+        result
+    }
+
+    /// Smallest root and exponent such that `root^exp == base`.
+    pub(crate) fn perfect_power(base: u32) -> (u32, u32) {
+        for exp in (2..32).rev() {
+            let root = (f64::from(base)).powf(1.0 / f64::from(exp)).round() as u64;
+            for root in root.saturating_sub(1).max(2)..=root + 1 {
+                if root.checked_pow(exp) == Some(u64::from(base)) {
+                    return (root as u32, exp);
+                }
+            }
+        }
+        (base, 1)
+    }
+
+    /// Conversion between `root^from_exp` and `root^to_exp` bases, which
+    /// is linear: every digit is split into `from_exp` digits in `root`,
+    /// which are then grouped by `to_exp`.
+    fn regroup(digits: &[u32], root: u32, from_exp: u32, to_exp: u32) -> Vec<u32> {
+        let root_digits = digits.iter().flat_map(|&digit| {
+            (0..from_exp).scan(digit, move |rest, _| {
+                let d = *rest % root;
+                *rest /= root;
+                Some(d)
+            })
+        });
+
+        let mut result = Vec::with_capacity(digits.len() * from_exp as usize / to_exp as usize + 1);
+        let (mut digit, mut power, mut count) = (0u32, 1u32, 0);
+        for d in root_digits {
+            digit += d * power;
+            count += 1;
+            if count == to_exp {
+                result.push(digit);
+                digit = 0;
+                power = 1;
+                count = 0;
+            } else {
+                power *= root;
+            }
+        }
+        result.push(digit);
+        trim(&mut result);
+        result
+    }
+
+    // schoolbook long division, divisor must not be zero
+    fn divide_magnitude(a: &[u32], b: &[u32], base: u32) -> (Vec<u32>, Vec<u32>) {
+        let mut quotient = vec![0; a.len()];
+        let mut remainder = vec![0];
+        for idx in (0..a.len()).rev() {
+            // remainder = remainder * base + next digit
+            if remainder == [0] {
+                remainder[0] = a[idx];
+            } else {
+                remainder.insert(0, a[idx]);
+            }
+
+            // largest digit q such that b * q <= remainder
+            let (mut lo, mut hi) = (0, base - 1);
+            while lo < hi {
+                let mid = lo + (hi - lo).div_ceil(2);
+                if cmp_magnitude(&multiply_by_digit(b, mid, base), &remainder) == Ordering::Greater
+                {
+                    hi = mid - 1;
+                } else {
+                    lo = mid;
+                }
+            }
+            if lo > 0 {
+                sub_magnitude(&mut remainder, &multiply_by_digit(b, lo, base), base);
+                trim(&mut remainder);
+            }
+            quotient[idx] = lo;
+        }
+        (quotient, remainder)
+    }
+
+    fn multiply_by_digit(a: &[u32], digit: u32, base: u32) -> Vec<u32> {
+        let mut result = multiply_magnitude(a, &[digit], base);
+        trim(&mut result);
+        result
+    }
+
+    // drop leading zeros, all zeroes become [0]
+    fn trim(digits: &mut Vec<u32>) {
+        let len = digits.iter().rposition(|&x| x > 0).map_or(1, |pos| pos + 1);
+        digits.truncate(len);
+        if digits.is_empty() {
+            digits.push(0);
+        }
+    }
 }
@@ -151,7 +151,10 @@ fn invalid_positive_digit() {
     let output_base = 10;
     assert_eq!(
         ayb::convert(input_digits, input_base, output_base),
-        Err(ayb::Error::InvalidDigit(2))
+        Err(ayb::Error::InvalidDigit {
+            digit: 2,
+            position: 1
+        })
     );
 }
 
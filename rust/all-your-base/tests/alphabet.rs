@@ -0,0 +1,160 @@
+use allyourbase::{Alphabet, Error};
+
+#[test]
+fn parse_hexadecimal() {
+    assert_eq!(
+        Ok(vec![2, 10, 15]),
+        allyourbase::parse("2af", 16, &Alphabet::BASE36)
+    );
+}
+
+#[test]
+fn parse_is_case_insensitive_for_base36() {
+    assert_eq!(
+        allyourbase::parse("dead", 16, &Alphabet::BASE36),
+        allyourbase::parse("DEAD", 16, &Alphabet::BASE36)
+    );
+}
+
+#[test]
+fn parse_keeps_leading_zeros() {
+    assert_eq!(
+        Ok(vec![0, 0, 1]),
+        allyourbase::parse("001", 2, &Alphabet::BASE36)
+    );
+    assert_eq!(Ok(vec![]), allyourbase::parse("", 2, &Alphabet::BASE36));
+}
+
+#[test]
+fn parse_reports_position_of_invalid_digit() {
+    assert_eq!(
+        Err(Error::InvalidDigit {
+            digit: 8,
+            position: 2
+        }),
+        allyourbase::parse("178", 8, &Alphabet::BASE36)
+    );
+}
+
+#[test]
+fn parse_reports_position_of_unknown_character() {
+    assert_eq!(
+        Err(Error::InvalidCharacter {
+            character: '_',
+            position: 3
+        }),
+        allyourbase::parse("123_456", 10, &Alphabet::BASE36)
+    );
+}
+
+#[test]
+fn position_is_byte_offset() {
+    let alphabet = Alphabet::new("αβγ").unwrap();
+    assert_eq!(
+        Err(Error::InvalidCharacter {
+            character: 'x',
+            position: 4
+        }),
+        allyourbase::parse("αβx", 3, &alphabet)
+    );
+}
+
+#[test]
+fn base_larger_than_alphabet() {
+    assert_eq!(
+        Err(Error::InvalidInputBase),
+        allyourbase::parse("1", 37, &Alphabet::BASE36)
+    );
+    assert_eq!(
+        Err(Error::InvalidInputBase),
+        allyourbase::parse("1", 1, &Alphabet::BASE36)
+    );
+}
+
+#[test]
+fn format_hexadecimal() {
+    assert_eq!(
+        Ok("ff".to_string()),
+        allyourbase::format(&[15, 15], &Alphabet::BASE36)
+    );
+}
+
+#[test]
+fn format_reports_position_of_invalid_digit() {
+    assert_eq!(
+        Err(Error::InvalidDigit {
+            digit: 62,
+            position: 1
+        }),
+        allyourbase::format(&[1, 62], &Alphabet::BASE62)
+    );
+}
+
+#[test]
+fn base58_bitcoin() {
+    let bytes: Vec<u32> = b"Hello World".iter().map(|&b| b.into()).collect();
+    let digits = allyourbase::convert(&bytes, 256, 58).unwrap();
+    assert_eq!(
+        Ok("JxF12TrwUP45BMd".to_string()),
+        allyourbase::format(&digits, &Alphabet::BASE58)
+    );
+    assert_eq!(
+        Err(Error::InvalidCharacter {
+            character: '0',
+            position: 0
+        }),
+        allyourbase::parse("0", 58, &Alphabet::BASE58)
+    );
+}
+
+#[test]
+fn base62_is_case_sensitive() {
+    assert_eq!(
+        Ok(vec![10, 36]),
+        allyourbase::parse("Aa", 62, &Alphabet::BASE62)
+    );
+}
+
+#[test]
+fn crockford_base32() {
+    assert_eq!(
+        Ok("1Z".to_string()),
+        allyourbase::format(&[1, 31], &Alphabet::CROCKFORD32)
+    );
+    assert_eq!(
+        Ok(vec![1, 0, 1, 1, 31]),
+        allyourbase::parse("i-o-L-1-z", 32, &Alphabet::CROCKFORD32)
+    );
+    assert_eq!(
+        Err(Error::InvalidCharacter {
+            character: 'U',
+            position: 0
+        }),
+        allyourbase::parse("U", 32, &Alphabet::CROCKFORD32)
+    );
+}
+
+#[test]
+fn custom_alphabet_round_trip() {
+    let dna = Alphabet::new("ACGT").unwrap();
+    let digits = allyourbase::parse("GATTACA", 4, &dna).unwrap();
+    let decimal = allyourbase::convert(&digits, 4, 10).unwrap();
+    assert_eq!(
+        Ok("9156".to_string()),
+        allyourbase::format(&decimal, &Alphabet::BASE36)
+    );
+    let back = allyourbase::convert(&decimal, 10, 4).unwrap();
+    assert_eq!(Ok("GATTACA".to_string()), allyourbase::format(&back, &dna));
+}
+
+#[test]
+fn repeated_symbol() {
+    assert_eq!(Err(Error::InvalidAlphabet), Alphabet::new("abca"));
+}
+
+#[test]
+fn too_few_symbols() {
+    assert_eq!(Err(Error::InvalidAlphabet), Alphabet::new(""));
+    assert_eq!(Err(Error::InvalidAlphabet), Alphabet::new("a"));
+    assert!(Alphabet::new("ab").is_ok());
+}
@@ -0,0 +1,330 @@
+use allyourbase::{Error, Number};
+
+fn n(value: i64) -> Number {
+    Number::from_i64(10, value).unwrap()
+}
+
+fn big(base: u32, digits: &[u32]) -> Number {
+    Number::from_digits(base, digits).unwrap()
+}
+
+#[test]
+fn invalid_base() {
+    assert_eq!(Err(Error::InvalidInputBase), Number::new(1));
+    assert_eq!(Err(Error::InvalidInputBase), Number::from_i64(0, 5));
+}
+
+#[test]
+fn zero() {
+    let zero = Number::new(7).unwrap();
+    assert!(zero.is_zero());
+    assert_eq!(vec![0], zero.digits());
+    assert_eq!(0, zero.signum());
+    assert_eq!(n(0), Number::from_u32(3, 0).unwrap());
+}
+
+#[test]
+fn zero_is_never_negative() {
+    assert!(!(-n(0)).is_negative());
+    assert!(!(n(5) - &n(5)).is_negative());
+    assert!(!(n(-3) * &n(0)).is_negative());
+}
+
+#[test]
+fn sign() {
+    assert_eq!(-1, n(-42).signum());
+    assert_eq!(1, n(42).signum());
+    assert!(n(-42).is_negative());
+    assert_eq!(vec![4, 2], n(-42).digits());
+    assert_eq!(n(42), n(-42).abs());
+    assert_eq!(n(42), -n(-42));
+}
+
+#[test]
+fn from_i64_extremes() {
+    let min = Number::from_i64(16, i64::MIN).unwrap();
+    assert!(min.is_negative());
+    assert_eq!(
+        vec![8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        min.digits()
+    );
+    assert_eq!(
+        vec![1, 8, 4, 4, 6, 7, 4, 4, 0, 7, 3, 7, 0, 9, 5, 5, 1, 6, 1, 5],
+        Number::from_u64(10, u64::MAX).unwrap().digits()
+    );
+}
+
+#[test]
+fn addition_with_signs() {
+    assert_eq!(n(12), n(5) + &n(7));
+    assert_eq!(n(-2), n(5) + &n(-7));
+    assert_eq!(n(2), n(-5) + &n(7));
+    assert_eq!(n(-12), n(-5) + &n(-7));
+}
+
+#[test]
+fn subtraction() {
+    assert_eq!(n(-2), n(5) - &n(7));
+    assert_eq!(n(12), n(5) - &n(-7));
+    assert_eq!(n(-12), n(-5) - &n(7));
+    assert_eq!(n(99), n(1000) - &n(901));
+    let mut x = n(1);
+    x -= &n(1);
+    assert!(x.is_zero());
+}
+
+#[test]
+fn operands_in_different_bases() {
+    let hex = Number::from_i64(16, 255).unwrap();
+    let sum = n(1) + &hex;
+    assert_eq!(10, sum.base());
+    assert_eq!(vec![2, 5, 6], sum.digits());
+    assert_eq!(hex, n(255));
+}
+
+#[test]
+fn multiplication() {
+    assert_eq!(n(-56), n(7) * &n(-8));
+    assert_eq!(n(56), n(-7) * &n(-8));
+    assert_eq!(n(-21), n(-7) * 3);
+}
+
+#[test]
+fn multiplication_in_large_base() {
+    let base = u32::MAX;
+    let x = big(base, &[base - 1, base - 1]);
+    let square = x.clone() * &x;
+    assert_eq!(vec![base - 1, base - 2, 0, 1], square.digits());
+}
+
+#[test]
+fn division_truncates_towards_zero() {
+    for &(a, b) in &[
+        (7, 2),
+        (-7, 2),
+        (7, -2),
+        (-7, -2),
+        (0, 5),
+        (3, 10),
+        (100, 10),
+    ] {
+        let (q, r) = n(a).div_rem(&n(b));
+        assert_eq!(n(a / b), q, "{} / {}", a, b);
+        assert_eq!(n(a % b), r, "{} % {}", a, b);
+        assert_eq!(n(a / b), n(a) / &n(b));
+        assert_eq!(n(a % b), n(a) % &n(b));
+    }
+}
+
+#[test]
+fn division_of_big_numbers() {
+    // (10^30 + 7) / (10^15 + 3)
+    let ten = n(10);
+    let a = ten.pow(30) + &n(7);
+    let b = ten.pow(15) + &n(3);
+    let (q, r) = a.div_rem(&b);
+    assert_eq!(q.clone() * &b + &r, a);
+    assert!(r < b);
+    assert_eq!(n(999_999_999_999_997), q);
+    assert_eq!(n(16), r);
+}
+
+#[test]
+fn division_by_zero() {
+    assert_eq!(None, n(5).checked_div_rem(&n(0)));
+}
+
+#[test]
+#[should_panic]
+fn division_by_zero_panics() {
+    let _ = n(5) / &n(0);
+}
+
+#[test]
+fn ordering() {
+    let mut numbers = vec![n(3), n(-10), n(0), n(100), n(-2), n(25)];
+    numbers.sort();
+    assert_eq!(vec![n(-10), n(-2), n(0), n(3), n(25), n(100)], numbers);
+    assert!(Number::from_i64(2, 9).unwrap() > n(8));
+    assert!(n(-1000) < n(-999));
+}
+
+#[test]
+fn power() {
+    assert_eq!(n(1), n(5).pow(0));
+    assert_eq!(n(-8), n(-2).pow(3));
+    assert_eq!(n(16), n(-2).pow(4));
+    let two_to_100 = Number::from_i64(2, 2).unwrap().pow(100);
+    let mut expected = vec![1];
+    expected.extend(vec![0; 100]);
+    assert_eq!(expected, two_to_100.digits());
+    assert_eq!(
+        vec![
+            1, 2, 6, 7, 6, 5, 0, 6, 0, 0, 2, 2, 8, 2, 2, 9, 4, 0, 1, 4, 9, 6, 7, 0, 3, 2, 0, 5, 3,
+            7, 6
+        ],
+        two_to_100.convert(10).unwrap().digits()
+    );
+}
+
+#[test]
+fn values_beyond_u64() {
+    let max = Number::from_u64(10, u64::MAX).unwrap();
+    let square = max.clone() * &max;
+    assert_eq!(max, square.clone() / &max);
+    assert_eq!(square - &max, max.clone() * &(max.clone() - &n(1)));
+}
+
+#[test]
+fn negative_conversion_keeps_sign() {
+    let x = n(-42).convert(2).unwrap();
+    assert!(x.is_negative());
+    assert_eq!(vec![1, 0, 1, 0, 1, 0], x.digits());
+}
+
+// Large numbers
+
+// deterministic pseudo-random digits, most significant first, no leading zero
+fn digits(base: u32, len: usize, seed: u64) -> Vec<u32> {
+    let mut state = seed;
+    let mut digits: Vec<u32> = (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            ((state >> 33) % u64::from(base)) as u32
+        })
+        .collect();
+    digits[0] = digits[0].max(1);
+    digits
+}
+
+// the straightforward algorithm: sum of digit * from_base^i in to_base
+fn power_summation(digits: &[u32], from_base: u32, to_base: u32) -> Vec<u32> {
+    let base = Number::from_u32(to_base, from_base).unwrap();
+    let mut power = Number::from_u32(to_base, 1).unwrap();
+    let mut result = Number::new(to_base).unwrap();
+    for &digit in digits.iter().rev() {
+        result += &(power.clone() * digit);
+        power *= &base;
+    }
+    result.digits()
+}
+
+#[test]
+fn large_conversion_matches_power_summation() {
+    for &(from_base, to_base, len) in &[(10, 2, 300), (7, 1000, 500), (256, 10, 200), (3, 5, 1000)]
+    {
+        let input = digits(from_base, len, len as u64);
+        assert_eq!(
+            Ok(power_summation(&input, from_base, to_base)),
+            allyourbase::convert(&input, from_base, to_base),
+            "{} -> {}",
+            from_base,
+            to_base
+        );
+    }
+}
+
+#[test]
+fn large_conversion_round_trip() {
+    let input = digits(10, 5_000, 42);
+    let binary = allyourbase::convert(&input, 10, 2).unwrap();
+    let septenary = allyourbase::convert(&binary, 2, 7).unwrap();
+    assert_eq!(Ok(input), allyourbase::convert(&septenary, 7, 10));
+}
+
+#[test]
+fn large_multiplication() {
+    // (10^n - 1)^2 = 10^2n - 2 * 10^n + 1 = 99..9800..01
+    let n = 1000;
+    let nines = big(10, &vec![9; n]);
+    let mut expected = vec![9; n - 1];
+    expected.push(8);
+    expected.extend(vec![0; n - 1]);
+    expected.push(1);
+    assert_eq!(expected, (nines.clone() * &nines).digits());
+}
+
+#[test]
+fn large_multiplication_of_unbalanced_numbers() {
+    let a = big(1000, &digits(1000, 700, 1));
+    let b = big(1000, &digits(1000, 90, 2));
+    let product = a.clone() * &b;
+    assert_eq!(a, product.clone() / &b);
+    assert!((product % &a).is_zero());
+}
+
+#[test]
+fn bases_which_are_powers_of_the_same_number() {
+    for &(from_base, to_base) in &[
+        (2, 16),
+        (16, 8),
+        (8, 2),
+        (10, 100),
+        (1000, 100),
+        (4, 8),
+        (9, 27),
+        (2, 1 << 31),
+    ] {
+        let mut input = vec![0, 0];
+        input.extend(digits(from_base, 97, u64::from(to_base)));
+        let mut expected = power_summation(&input, from_base, to_base);
+        if expected.is_empty() {
+            expected.push(0);
+        }
+        assert_eq!(
+            Ok(expected),
+            allyourbase::convert(&input, from_base, to_base),
+            "{} -> {}",
+            from_base,
+            to_base
+        );
+    }
+}
+
+#[test]
+fn regrouping_keeps_zero() {
+    assert_eq!(Ok(vec![0]), allyourbase::convert(&[0, 0, 0], 2, 16));
+    assert_eq!(
+        Ok(vec![1, 0]),
+        allyourbase::convert(&[1, 0, 0, 0, 0], 2, 16)
+    );
+}
+
+#[test]
+fn display_in_decimal() {
+    assert_eq!("0", Number::new(16).unwrap().to_string());
+    assert_eq!("-255", (-big(16, &[15, 15])).to_string());
+    assert_eq!("1234", n(1234).to_string());
+    assert_eq!("+00042", format!("{:+06}", big(2, &[1, 0, 1, 0, 1, 0])));
+}
+
+#[test]
+fn parse_decimal() {
+    assert_eq!(Ok(n(-1234)), "-1234".parse());
+    assert_eq!(Ok(n(7)), "+007".parse());
+    let big: Number = "123456789012345678901234567890".parse().unwrap();
+    assert_eq!("123456789012345678901234567890", big.to_string());
+    assert_eq!(10, big.base());
+}
+
+#[test]
+fn parse_invalid_decimal() {
+    assert_eq!(Err(Error::NoDigits), "".parse::<Number>());
+    assert_eq!(Err(Error::NoDigits), "-".parse::<Number>());
+    assert_eq!(
+        Err(Error::InvalidDigit {
+            digit: 10,
+            position: 3
+        }),
+        "-12a".parse::<Number>()
+    );
+    assert_eq!(
+        Err(Error::InvalidCharacter {
+            character: ' ',
+            position: 1
+        }),
+        "1 2".parse::<Number>()
+    );
+}
@@ -0,0 +1,157 @@
+use allyourbase::{self as ayb, Alphabet, Error};
+
+const DURATION: &[u32] = &[24, 60, 60];
+
+#[test]
+fn duration_to_seconds() {
+    assert_eq!(
+        Ok(vec![9, 3, 6, 0, 5]),
+        ayb::from_mixed_radix(&[1, 2, 0, 5], DURATION, 10)
+    );
+}
+
+#[test]
+fn seconds_to_duration() {
+    assert_eq!(
+        Ok(vec![1, 2, 0, 5]),
+        ayb::to_mixed_radix(&[9, 3, 6, 0, 5], 10, DURATION)
+    );
+}
+
+#[test]
+fn most_significant_digit_is_unbounded() {
+    assert_eq!(
+        Ok(vec![400, 0, 0, 0]),
+        ayb::to_mixed_radix(&[3, 4, 5, 6, 0, 0, 0, 0], 10, DURATION)
+    );
+    assert_eq!(
+        Ok(vec![3, 4, 5, 6, 0, 0, 0, 0]),
+        ayb::from_mixed_radix(&[400, 0, 0, 0], DURATION, 10)
+    );
+}
+
+#[test]
+fn missing_leading_digits_are_zeros() {
+    assert_eq!(
+        Ok(vec![3, 3, 0]),
+        ayb::from_mixed_radix(&[5, 30], DURATION, 10)
+    );
+    assert_eq!(Ok(vec![0]), ayb::from_mixed_radix(&[], DURATION, 10));
+    assert_eq!(Ok(vec![0, 0, 0, 0]), ayb::to_mixed_radix(&[], 10, DURATION));
+}
+
+#[test]
+fn mixed_radix_digit_out_of_range() {
+    assert_eq!(
+        Err(Error::InvalidDigit {
+            digit: 60,
+            position: 1
+        }),
+        ayb::from_mixed_radix(&[5, 60, 0], DURATION, 10)
+    );
+}
+
+#[test]
+fn too_many_mixed_radix_digits() {
+    assert_eq!(
+        Err(Error::TooManyDigits { count: 5, max: 4 }),
+        ayb::from_mixed_radix(&[1, 0, 0, 0, 0], DURATION, 10)
+    );
+}
+
+#[test]
+fn invalid_radix() {
+    assert_eq!(
+        Err(Error::InvalidInputBase),
+        ayb::from_mixed_radix(&[1], &[24, 1], 10)
+    );
+    assert_eq!(
+        Err(Error::InvalidOutputBase),
+        ayb::to_mixed_radix(&[1], 10, &[0])
+    );
+    assert_eq!(
+        Err(Error::InvalidOutputBase),
+        ayb::from_mixed_radix(&[1], DURATION, 1)
+    );
+}
+
+#[test]
+fn most_significant_digit_overflow() {
+    let large = ayb::convert(&[1, 0, 0, 0, 0, 0, 0, 0, 0], 16, 10).unwrap();
+    assert_eq!(Err(Error::Overflow), ayb::to_mixed_radix(&large, 10, &[]));
+}
+
+#[test]
+fn spreadsheet_columns() {
+    let columns = Alphabet::new("_ABCDEFGHIJKLMNOPQRSTUVWXYZ").unwrap();
+    for &(column, number) in &[
+        ("A", 1),
+        ("Z", 26),
+        ("AA", 27),
+        ("AZ", 52),
+        ("BA", 53),
+        ("ZZ", 702),
+        ("AAA", 703),
+        ("XFD", 16384),
+    ] {
+        let decimal = ayb::convert(&[number], 100_000, 10).unwrap();
+        let bijective = ayb::to_bijective(&decimal, 10, 26).unwrap();
+        assert_eq!(Ok(column.to_string()), ayb::format(&bijective, &columns));
+        assert_eq!(Ok(decimal), ayb::from_bijective(&bijective, 26, 10));
+    }
+}
+
+#[test]
+fn bijective_zero_is_empty() {
+    assert_eq!(Ok(vec![]), ayb::to_bijective(&[0], 10, 26));
+    assert_eq!(Ok(vec![0]), ayb::from_bijective(&[], 26, 10));
+}
+
+#[test]
+fn bijective_base_10() {
+    assert_eq!(Ok(vec![10]), ayb::to_bijective(&[1, 0], 10, 10));
+    assert_eq!(Ok(vec![9, 10]), ayb::to_bijective(&[1, 0, 0], 10, 10));
+    assert_eq!(Ok(vec![1, 0, 0]), ayb::from_bijective(&[9, 10], 10, 10));
+}
+
+#[test]
+fn bijective_round_trip() {
+    for n in 0..2000 {
+        let digits = ayb::convert(&[n], 10_000, 3).unwrap();
+        let bijective = ayb::to_bijective(&digits, 3, 2).unwrap();
+        assert!(bijective.iter().all(|&d| d == 1 || d == 2));
+        assert_eq!(Ok(digits), ayb::from_bijective(&bijective, 2, 3));
+    }
+}
+
+#[test]
+fn bijective_with_largest_k() {
+    let k = u32::MAX;
+    // k * k + k
+    let decimal = vec![1, 8, 4, 4, 6, 7, 4, 4, 0, 6, 9, 4, 1, 4, 5, 8, 4, 3, 2, 0];
+    assert_eq!(Ok(decimal.clone()), ayb::from_bijective(&[k, k], k, 10));
+    assert_eq!(Ok(vec![k, k]), ayb::to_bijective(&decimal, 10, k));
+    assert_eq!(Ok(vec![1, k]), ayb::to_bijective(&[2, 0], k, k));
+}
+
+#[test]
+fn bijective_has_no_zero_digit() {
+    assert_eq!(
+        Err(Error::InvalidDigit {
+            digit: 0,
+            position: 1
+        }),
+        ayb::from_bijective(&[1, 0], 26, 10)
+    );
+    assert_eq!(
+        Err(Error::InvalidDigit {
+            digit: 27,
+            position: 0
+        }),
+        ayb::from_bijective(&[27], 26, 10)
+    );
+    assert_eq!(
+        Err(Error::InvalidInputBase),
+        ayb::from_bijective(&[1], 1, 10)
+    );
+}
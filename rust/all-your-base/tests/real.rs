@@ -0,0 +1,118 @@
+use allyourbase::{Alphabet, Converted, Error, Real};
+
+fn convert(s: &str, from_base: u32, to_base: u32, max_fraction_digits: usize) -> (String, bool) {
+    let real = Real::parse(s, from_base, &Alphabet::BASE36).unwrap();
+    let Converted { number, truncated } = real
+        .convert(from_base, to_base, max_fraction_digits)
+        .unwrap();
+    (number.format(&Alphabet::BASE36).unwrap(), truncated)
+}
+
+#[test]
+fn negative_binary_to_decimal() {
+    assert_eq!(
+        ("-5.375".to_string(), false),
+        convert("-101.011", 2, 10, 10)
+    );
+}
+
+#[test]
+fn negative_binary_to_hexadecimal() {
+    assert_eq!(("-5.6".to_string(), false), convert("-101.011", 2, 16, 10));
+}
+
+#[test]
+fn non_terminating_fraction_is_truncated() {
+    assert_eq!(("0.00011001".to_string(), true), convert("0.1", 10, 2, 8));
+    assert_eq!(("0.33333".to_string(), true), convert("0.1", 3, 10, 5));
+}
+
+#[test]
+fn terminating_fraction_within_limit() {
+    assert_eq!(("0.1".to_string(), false), convert("0.2", 6, 3, 1));
+}
+
+#[test]
+fn no_fraction_digits() {
+    assert_eq!(("2".to_string(), true), convert("2.5", 10, 10, 0));
+    assert_eq!(("ff".to_string(), false), convert("255", 10, 16, 0));
+}
+
+#[test]
+fn trailing_and_leading_zeros_are_dropped() {
+    assert_eq!(
+        ("12.5".to_string(), false),
+        convert("0012.5000", 10, 10, 10)
+    );
+}
+
+#[test]
+fn zero_is_never_negative() {
+    assert_eq!(("0".to_string(), false), convert("-0.0", 2, 10, 10));
+    assert_eq!(("0".to_string(), true), convert("-0.1", 3, 10, 0));
+}
+
+#[test]
+fn parse_sign_and_point() {
+    assert_eq!(
+        Ok(Real {
+            negative: false,
+            integer: vec![],
+            fraction: vec![5],
+        }),
+        Real::parse("+.5", 10, &Alphabet::BASE36)
+    );
+    assert_eq!(
+        Ok(Real {
+            negative: true,
+            integer: vec![1, 15],
+            fraction: vec![],
+        }),
+        Real::parse("-1f", 16, &Alphabet::BASE36)
+    );
+}
+
+#[test]
+fn parse_reports_byte_offset() {
+    assert_eq!(
+        Err(Error::InvalidDigit {
+            digit: 2,
+            position: 5
+        }),
+        Real::parse("-10.12", 2, &Alphabet::BASE36)
+    );
+    assert_eq!(
+        Err(Error::InvalidCharacter {
+            character: '.',
+            position: 3
+        }),
+        Real::parse("1.0.1", 2, &Alphabet::BASE36)
+    );
+}
+
+#[test]
+fn convert_reports_digit_position() {
+    let real = Real {
+        negative: false,
+        integer: vec![1, 0],
+        fraction: vec![1, 2],
+    };
+    assert_eq!(
+        Err(Error::InvalidDigit {
+            digit: 2,
+            position: 3
+        }),
+        real.convert(2, 10, 5)
+    );
+    assert_eq!(Err(Error::InvalidOutputBase), real.convert(3, 1, 5));
+}
+
+#[test]
+fn format_without_integer_digits() {
+    let real = Real {
+        negative: true,
+        integer: vec![],
+        fraction: vec![2, 5],
+    };
+    assert_eq!(Ok("-0.25".to_string()), real.format(&Alphabet::BASE36));
+}
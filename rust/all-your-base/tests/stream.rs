@@ -0,0 +1,134 @@
+use allyourbase::{self as ayb, Error};
+
+// digits most significant first, like `convert`
+fn convert_stream(digits: &[u32], from_base: u32, to_base: u32) -> Result<Vec<u32>, Error> {
+    let stream = ayb::convert_stream(digits.iter().rev().copied(), from_base, to_base)?;
+    let mut result = stream.collect::<Result<Vec<_>, _>>()?;
+    result.reverse();
+    Ok(result)
+}
+
+#[test]
+fn hexadecimal_to_octal() {
+    assert_eq!(Ok(vec![3, 7]), convert_stream(&[1, 15], 16, 8));
+}
+
+#[test]
+fn binary_to_bytes() {
+    assert_eq!(
+        Ok(vec![1, 255]),
+        convert_stream(&[1, 1, 1, 1, 1, 1, 1, 1, 1], 2, 256)
+    );
+}
+
+#[test]
+fn bytes_to_binary() {
+    assert_eq!(Ok(vec![1, 0, 1]), convert_stream(&[0, 5], 256, 2));
+}
+
+#[test]
+fn same_as_convert() {
+    let digits: Vec<u32> = (0..100).map(|i| i * 7 % 16).collect();
+    for &(from_base, to_base) in &[
+        (16, 8),
+        (16, 2),
+        (16, 256),
+        (16, 4096),
+        (16, 16),
+        (2, 1 << 31),
+    ] {
+        let from = ayb::convert(&digits, 16, from_base).unwrap();
+        assert_eq!(
+            ayb::convert(&from, from_base, to_base),
+            convert_stream(&from, from_base, to_base),
+            "{} -> {}",
+            from_base,
+            to_base
+        );
+    }
+}
+
+#[test]
+fn powers_of_other_numbers() {
+    assert_eq!(
+        Ok(vec![12, 345]),
+        convert_stream(&[1, 2, 3, 4, 5], 10, 1000)
+    );
+    assert_eq!(Ok(vec![2, 1, 1, 0]), convert_stream(&[7, 3], 9, 3));
+}
+
+#[test]
+fn leading_zeros_are_dropped() {
+    assert_eq!(Ok(vec![1]), convert_stream(&[0, 0, 0, 1], 2, 16));
+    assert_eq!(Ok(vec![1, 0, 0]), convert_stream(&[0, 4], 16, 2));
+}
+
+#[test]
+fn zero() {
+    assert_eq!(Ok(vec![0]), convert_stream(&[], 2, 16));
+    assert_eq!(Ok(vec![0]), convert_stream(&[0, 0], 2, 16));
+}
+
+#[test]
+fn stream_is_lazy() {
+    let ones = ayb::convert_stream(std::iter::repeat(15), 16, 2).unwrap();
+    assert!(ones.take(1000).all(|digit| digit == Ok(1)));
+}
+
+#[test]
+fn invalid_digit_ends_stream() {
+    let mut stream = ayb::convert_stream(vec![1, 0, 2, 1], 2, 4).unwrap();
+    assert_eq!(Some(Ok(1)), stream.next());
+    assert_eq!(
+        Some(Err(Error::InvalidDigit {
+            digit: 2,
+            position: 2
+        })),
+        stream.next()
+    );
+    assert_eq!(None, stream.next());
+}
+
+#[test]
+fn incompatible_bases() {
+    assert!(matches!(
+        ayb::convert_stream(vec![1], 10, 2),
+        Err(Error::IncompatibleBases)
+    ));
+    assert!(matches!(
+        ayb::convert_stream(vec![1], 8, 6),
+        Err(Error::IncompatibleBases)
+    ));
+}
+
+#[test]
+fn invalid_bases() {
+    assert!(matches!(
+        ayb::convert_stream(vec![1], 1, 2),
+        Err(Error::InvalidInputBase)
+    ));
+    assert!(matches!(
+        ayb::convert_stream(vec![1], 2, 0),
+        Err(Error::InvalidOutputBase)
+    ));
+}
+
+#[test]
+fn input_is_not_read_after_its_end() {
+    // yields 1, then None, then 3 and so on
+    let mut count = 0;
+    let unfused = std::iter::from_fn(move || {
+        count += 1;
+        if count % 2 == 0 {
+            None
+        } else {
+            Some(count)
+        }
+    });
+    assert_eq!(
+        Ok(vec![1]),
+        ayb::convert_stream(unfused, 4, 16)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+    );
+}
@@ -1,255 +1,801 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
-use std::collections::HashSet;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+pub use parser::ParseError;
+
+/// Why a puzzle cannot be solved at all.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PuzzleError {
+    /// Input is not a valid puzzle.
+    Malformed(ParseError),
+    /// Equation starting at the byte offset has no `==`.
+    MissingEquals { position: usize },
+    /// More distinct letters than digits in the radix.
+    TooManyLetters { letters: usize, radix: u8 },
+    /// Radix is not in the range from 2 to 36.
+    InvalidRadix(u8),
+}
+
+/// Solve a puzzle, `Ok(None)` if it has no solution.
+///
+/// Puzzle is one or more equations separated by newlines, `,` or `;`,
+/// f.e. `"SEND + MORE == MONEY"` or `"AB * C == DEF, A + B == C"`.
+pub fn solve(input: &str) -> Result<Option<Solution>, PuzzleError> {
+    Ok(Puzzle::parse(input)?.solve())
+}
 
-pub fn solve(input: &str) -> Option<HashMap<char, u8>> {
-    let puzzle = AlphameticPuzzle::from(input);
-    puzzle.find_solution2()
+/// All solutions of a puzzle, see `Puzzle::solve_all`.
+pub fn solve_all(input: &str) -> Result<Solutions<'static>, PuzzleError> {
+    Ok(Solutions::new(Cow::Owned(Puzzle::parse(input)?)))
+}
+
+/// Number of solutions of a puzzle up to `limit`, see `Puzzle::count_solutions`.
+pub fn count_solutions(input: &str, limit: usize) -> Result<usize, PuzzleError> {
+    Ok(Puzzle::parse(input)?.count_solutions(limit))
+}
+
+/// Uniquely solvable `A + B == C` puzzles in the given radix made of
+/// words from the list, generated lazily.
+///
+/// Addends are in the order of the list and the sum is a different word.
+/// Words which are not valid in a puzzle, f.e. with digits, are skipped.
+pub fn generate<'a>(
+    words: &'a [&'a str],
+    radix: u8,
+) -> Result<impl Iterator<Item = String> + 'a, PuzzleError> {
+    check_radix(radix)?;
+
+    let len = |word: &str| word.chars().count();
+    let n = words.len();
+
+    Ok((0..n)
+        .flat_map(move |a| (a..n).flat_map(move |b| (0..n).map(move |c| (a, b, c))))
+        .filter(move |&(a, b, c)| {
+            // sum without leading zeros has at most one more digit
+            let longest = len(words[a]).max(len(words[b]));
+            c != a && c != b && (longest..=longest + 1).contains(&len(words[c]))
+        })
+        .map(move |(a, b, c)| format!("{} + {} == {}", words[a], words[b], words[c]))
+        .filter(move |puzzle| {
+            Puzzle::parse_radix(puzzle, radix).is_ok_and(|p| p.count_solutions(2) == 1)
+        }))
+}
+
+fn check_radix(radix: u8) -> Result<(), PuzzleError> {
+    if (2..=36).contains(&radix) {
+        Ok(())
+    } else {
+        Err(PuzzleError::InvalidRadix(radix))
+    }
 }
 
+/// Parsed puzzle prepared for the search.
 #[derive(Clone, Debug)]
-struct AlphameticPuzzle {
-    leading_letters: HashSet<char>,
-    even_letters: HashSet<char>,
-    summands: AlphameticNumbersSum,
-    sum: AlphameticNumber,
+pub struct Puzzle {
+    // letters in the order of the search, least significant columns first
+    // so that wrong assignments fail (later) on partial checks earlier
     letters: Vec<char>,
+
+    // letters which start a word, by index in `letters`
+    leading: Vec<bool>,
+
+    // checks[n] holds once the first n letters are assigned
+    checks: Vec<Vec<Check>>,
+
+    radix: u8,
+
+    // input for rendering solutions
+    source: Arc<str>,
 }
 
-impl AlphameticPuzzle {
-    fn from(expression: &str) -> Self {
-        // "AA + BB + DD == CC" -> "AA", "BB", "DD", "CC"
-        let mut numbers = expression
-            .split(|c: char| !c.is_alphabetic())
-            .filter(|&str| str.len() > 0)
-            .map(|str| str.chars().into_iter().rev().collect::<Vec<_>>())
-            .collect::<Vec<_>>();
+impl Puzzle {
+    pub fn parse(input: &str) -> Result<Self, PuzzleError> {
+        Puzzle::parse_radix(input, 10)
+    }
+
+    /// Puzzle with numbers in the given radix from 2 to 36, f.e. 16 for
+    /// hexadecimal.
+    pub fn parse_radix(input: &str, radix: u8) -> Result<Self, PuzzleError> {
+        check_radix(radix)?;
 
-        let maxlen = numbers.iter().map(|str| str.len()).max().unwrap();
+        let equations = parser::parse(input)?;
 
-        let mut unique_letters = HashSet::new();
         let mut letters = Vec::new();
-        let mut leading_letters = HashSet::new();
-
-        // we enumberate letters starting from units column
-        // so to fail (later) on wrong permutation earlier
-        for idx in 0..maxlen {
-            for num in &numbers {
-                if let Some(letter) = num.get(idx) {
-                    if !unique_letters.contains(letter) {
-                        unique_letters.insert(*letter);
-                        letters.push(*letter);
+        let mut leading_letters = Vec::new();
+        let mut polynomials = Vec::with_capacity(equations.len());
+        for (lhs, rhs) in &equations {
+            for word in lhs.words().chain(rhs.words()) {
+                for &letter in word {
+                    if !letters.contains(&letter) {
+                        letters.push(letter);
                     }
                 }
+                leading_letters.push(word[0]);
+            }
+
+            let difference = Polynomial::from(lhs, radix)?.sub(&Polynomial::from(rhs, radix)?)?;
+            if difference.bound(radix).is_none() {
+                return Err(ParseError::Overflow.into());
             }
+            polynomials.push(difference);
+        }
+
+        if letters.len() > usize::from(radix) {
+            return Err(PuzzleError::TooManyLetters {
+                letters: letters.len(),
+                radix,
+            });
         }
 
-        // find all first letters
-        // later we will use them for discarding permutations
-        // with leading zeroes
-        for num in &numbers {
-            leading_letters.insert(num.last().copied().unwrap());
+        // letter which affects lower digits of an equation goes first
+        let column = |letter: &char| {
+            polynomials
+                .iter()
+                .flat_map(|p| p.terms.iter())
+                .filter(|(monomial, _)| monomial.contains(letter))
+                .map(|(_, &coefficient)| trailing_zeros(coefficient, radix))
+                .min()
+                .unwrap_or(usize::MAX)
+        };
+        letters.sort_by_key(column);
+
+        let index = |letter: &char| letters.iter().position(|l| l == letter).unwrap();
+        let mut checks = vec![Vec::new(); letters.len() + 1];
+        for polynomial in &polynomials {
+            let terms: Vec<_> = polynomial
+                .terms
+                .iter()
+                .map(|(monomial, &coefficient)| (coefficient, monomial.iter().map(index).collect()))
+                .collect();
+            for (depth, check) in Check::all(terms, radix) {
+                checks[depth].push(check);
+            }
         }
 
-        // separate the last number as sum
-        let sum_number = numbers.pop().unwrap();
-        let sum = AlphameticNumber::from(sum_number);
+        Ok(Puzzle {
+            leading: letters
+                .iter()
+                .map(|l| leading_letters.contains(l))
+                .collect(),
+            letters,
+            checks,
+            radix,
+            source: input.into(),
+        })
+    }
+
+    pub fn radix(&self) -> u8 {
+        self.radix
+    }
 
-        // prepare summands
-        let mut summands = AlphameticNumbersSum::new();
-        for num in numbers {
-            let num = AlphameticNumber::from(num);
-            summands += num;
+    fn solution(&self, digits: Vec<u8>) -> Solution {
+        Solution {
+            digits: self.letters.iter().copied().zip(digits).collect(),
+            radix: self.radix,
+            source: self.source.clone(),
         }
+    }
 
-        // find all letters which are supposed to be even
-        let mut even_letters = HashSet::new();
+    /// First solution in the order of the search, found by as many workers
+    /// as there are available cpus, see `solve_parallel`.
+    pub fn solve(&self) -> Option<Solution> {
+        let workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+        self.solve_parallel(workers)
+    }
 
-        for (idx, letters_count) in summands.letters_count.iter().enumerate() {
-            if letters_count.iter().all(|(_, &count)| count % 2 == 0) {
-                even_letters.insert(sum.letters[idx]);
+    /// All solutions, found lazily in the order of the search.
+    pub fn solve_all(&self) -> Solutions<'_> {
+        Solutions::new(Cow::Borrowed(self))
+    }
+
+    /// First solution in the order of the search. Branches of the search
+    /// by digits of the first letters are explored by `workers` threads.
+    ///
+    /// The result does not depend on the number of workers: it is the
+    /// first solution in the order of the search, which is the smallest
+    /// one comparing digits of letters in that order. Workers stop once
+    /// a solution is found in an earlier branch.
+    pub fn solve_parallel(&self, workers: usize) -> Option<Solution> {
+        if workers <= 1 {
+            return self.solve_all().next();
+        }
+
+        // enough branches to keep workers busy when some are pruned early
+        let mut prefixes = Vec::new();
+        for depth in 1..self.letters.len() {
+            let mut search = Solutions::between(Cow::Borrowed(self), Vec::new(), depth);
+            prefixes = std::iter::from_fn(|| search.search(|| false)).collect();
+            if prefixes.len() >= workers * 4 {
+                break;
             }
         }
 
-        summands.convert_to_columns();
+        if prefixes.is_empty() {
+            return self.solve_all().next();
+        }
 
-        dbg!(AlphameticPuzzle {
-            leading_letters,
-            even_letters,
-            summands,
-            sum,
-            letters,
+        let next_branch = AtomicUsize::new(0);
+        // smallest branch with a solution
+        let found = AtomicUsize::new(usize::MAX);
+        let (next_branch, found, prefixes) = (&next_branch, &found, &prefixes);
+
+        let solution = crossbeam::scope(|s| {
+            let threads: Vec<_> = (0..workers)
+                .map(|_| {
+                    s.spawn(move |_| {
+                        let mut solutions = Vec::new();
+                        loop {
+                            let branch = next_branch.fetch_add(1, Ordering::Relaxed);
+                            if branch >= prefixes.len() || branch > found.load(Ordering::Relaxed) {
+                                return solutions;
+                            }
+
+                            let end = self.letters.len();
+                            let prefix = prefixes[branch].clone();
+                            let mut search = Solutions::between(Cow::Borrowed(self), prefix, end);
+                            let cancelled = || found.load(Ordering::Relaxed) < branch;
+                            if let Some(digits) = search.search(cancelled) {
+                                found.fetch_min(branch, Ordering::Relaxed);
+                                solutions.push((branch, digits));
+                            }
+                        }
+                    })
+                })
+                .collect();
+
+            threads
+                .into_iter()
+                .flat_map(|thread| thread.join().unwrap())
+                .min()
         })
+        .unwrap();
+
+        let (_, digits) = solution?;
+        Some(self.solution(digits))
     }
 
-    fn find_solution2(&self) -> Option<HashMap<char, u8>> {
-        let mut solution = HashMap::with_capacity(self.letters.len());
-        let mut used_digits = HashSet::with_capacity(self.letters.len());
+    /// Number of solutions, but at most `limit`: the search stops when
+    /// that many are found. F.e. a puzzle is unique if it counts 1 of 2.
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        self.solve_all().take(limit).count()
+    }
+}
 
-        self.find_solution2_recursive(&mut solution, &mut used_digits)
+/// Iterator over solutions of a puzzle, see `Puzzle::solve_all`.
+#[derive(Clone, Debug)]
+pub struct Solutions<'a> {
+    puzzle: Cow<'a, Puzzle>,
+
+    // digits of the assigned letters, in the order of `puzzle.letters`
+    digits: Vec<u8>,
+    used_digits: u64,
+
+    // next digit to try for every letter assigned by the search
+    // and the current one
+    next_digits: Vec<u8>,
+
+    // number of letters in a solution
+    end: usize,
+}
+
+impl<'a> Solutions<'a> {
+    fn new(puzzle: Cow<'a, Puzzle>) -> Self {
+        let end = puzzle.letters.len();
+        Solutions::between(puzzle, Vec::new(), end)
     }
 
-    fn find_solution2_recursive(
-        &self,
-        solution: &mut HashMap<char, u8>,
-        used_digits: &mut HashSet<u8>,
-    ) -> Option<HashMap<char, u8>> {
-        if solution.len() >= self.letters.len() {
-            if self.test_solution(solution) {
-                return Some(solution.clone());
-            } else {
-                return None;
-            }
+    /// Search which keeps the digits of the first letters, which must pass
+    /// the checks, and assigns letters up to `end`.
+    fn between(puzzle: Cow<'a, Puzzle>, digits: Vec<u8>, end: usize) -> Self {
+        let next_digits = if digits.len() >= end { vec![] } else { vec![0] };
+
+        Solutions {
+            used_digits: digits.iter().fold(0, |used, digit| used | 1 << digit),
+            digits,
+            puzzle,
+            next_digits,
+            end,
         }
+    }
 
-        let letter = self.letters[solution.len()];
+    /// Digits of the next solution, the search stops when `cancelled`.
+    fn search(&mut self, cancelled: impl Fn() -> bool) -> Option<Vec<u8>> {
+        let puzzle = &*self.puzzle;
 
-        for digit in 0..10u8 {
-            if used_digits.contains(&digit) {
-                continue;
+        while let Some(&from) = self.next_digits.last() {
+            if cancelled() {
+                self.next_digits.clear();
+                return None;
             }
 
-            if digit == 0 && self.leading_letters.contains(&letter) {
-                continue;
-            }
+            let depth = self.digits.len();
+            let used_digits = self.used_digits;
+            let leading = puzzle.leading[depth];
+            let digit = (from..puzzle.radix)
+                .find(|&digit| used_digits & 1 << digit == 0 && !(digit == 0 && leading));
+
+            let digit = match digit {
+                Some(digit) => digit,
+                None => {
+                    // all digits tried, back to the previous letter
+                    self.next_digits.pop();
+                    if !self.next_digits.is_empty() {
+                        pop(&mut self.digits, &mut self.used_digits);
+                    }
+                    continue;
+                }
+            };
 
-            if digit % 2 == 1 && self.even_letters.contains(&letter) {
-                continue;
+            *self.next_digits.last_mut().unwrap() = digit + 1;
+            self.digits.push(digit);
+            self.used_digits |= 1 << digit;
+
+            if !puzzle.checks[depth + 1]
+                .iter()
+                .all(|check| check.holds(&self.digits))
+            {
+                pop(&mut self.digits, &mut self.used_digits);
+            } else if depth + 1 == self.end {
+                let digits = self.digits.clone();
+                pop(&mut self.digits, &mut self.used_digits);
+                return Some(digits);
+            } else {
+                self.next_digits.push(0);
             }
+        }
 
-            solution.insert(letter, digit);
-            used_digits.insert(digit);
+        None
+    }
+}
 
-            if !self.find_solution2_partial_test_ok(solution) {
-                solution.remove(&letter);
-                used_digits.remove(&digit);
-                continue;
-            }
+/// Unassign the last assigned letter.
+fn pop(digits: &mut Vec<u8>, used_digits: &mut u64) {
+    if let Some(digit) = digits.pop() {
+        *used_digits &= !(1 << digit);
+    }
+}
 
-            if let Some(solution) = self.find_solution2_recursive(solution, used_digits) {
-                return Some(solution);
-            }
+impl Iterator for Solutions<'_> {
+    type Item = Solution;
 
-            solution.remove(&letter);
-            used_digits.remove(&digit);
-        }
+    fn next(&mut self) -> Option<Self::Item> {
+        let digits = self.search(|| false)?;
+        Some(self.puzzle.solution(digits))
+    }
+}
 
-        None
+/// Digits of letters of a solved puzzle.
+///
+/// Displays the puzzle with letters replaced by digits,
+/// f.e. `9567 + 1085 == 10652`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    digits: HashMap<char, u8>,
+    radix: u8,
+    source: Arc<str>,
+}
+
+impl Solution {
+    pub fn digit(&self, letter: char) -> Option<u8> {
+        self.digits.get(&letter).copied()
+    }
+
+    pub fn digits(&self) -> &HashMap<char, u8> {
+        &self.digits
     }
+}
 
-    fn find_solution2_partial_test_ok(&self, solution: &HashMap<char, u8>) -> bool {
-        let mut shift = 0;
+impl From<Solution> for HashMap<char, u8> {
+    fn from(solution: Solution) -> Self {
+        solution.digits
+    }
+}
 
-        for idx in 0..self.sum.letters.len() {
-            if let Some(&sum) = solution.get(&self.sum.letters[idx]) {
-                if idx == self.summands.letters_count.len() {
-                    if sum > idx as u8 {
-                        return false;
-                    }
-                    return true;
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.source.chars() {
+            match self.digit(c) {
+                Some(digit) => {
+                    let digit = std::char::from_digit(digit.into(), self.radix.into()).unwrap();
+                    write!(f, "{}", digit.to_ascii_uppercase())?
                 }
+                None => write!(f, "{}", c)?,
+            }
+        }
+        Ok(())
+    }
+}
 
-                let mut rhs_sum = 0;
+/// Number of trailing zero digits.
+fn trailing_zeros(mut x: i128, radix: u8) -> usize {
+    let radix = i128::from(radix);
+    let mut count = 0;
+    while x != 0 && x % radix == 0 {
+        x /= radix;
+        count += 1;
+    }
+    count
+}
 
-                for (letter, count) in self.summands.letters_columns[idx].iter() {
-                    if let Some(&letter_value) = solution.get(letter) {
-                        rhs_sum += *count as u64 * letter_value as u64;
-                    } else {
-                        return true;
+/// Sum of `coefficient * letter * letter * ...` terms,
+/// an equation `lhs == rhs` holds when `lhs - rhs` is zero.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Polynomial {
+    // monomial is a sorted list of letters
+    terms: BTreeMap<Vec<char>, i128>,
+}
+
+impl Polynomial {
+    fn from(expr: &parser::Expr, radix: u8) -> Result<Self, ParseError> {
+        use parser::Expr::*;
+
+        match expr {
+            Word(letters) => {
+                let mut word = Polynomial::default();
+                let mut place = 1i128;
+                for (idx, &letter) in letters.iter().rev().enumerate() {
+                    if idx > 0 {
+                        place = place
+                            .checked_mul(radix.into())
+                            .ok_or(ParseError::Overflow)?;
                     }
+                    word.add_term(vec![letter], place)?;
                 }
+                Ok(word)
+            }
+            Add(lhs, rhs) => Polynomial::from(lhs, radix)?.add(&Polynomial::from(rhs, radix)?),
+            Sub(lhs, rhs) => Polynomial::from(lhs, radix)?.sub(&Polynomial::from(rhs, radix)?),
+            Mul(lhs, rhs) => Polynomial::from(lhs, radix)?.mul(&Polynomial::from(rhs, radix)?),
+        }
+    }
 
-                rhs_sum += shift;
+    fn add_term(&mut self, monomial: Vec<char>, coefficient: i128) -> Result<(), ParseError> {
+        let entry = self.terms.entry(monomial).or_insert(0);
+        *entry = entry.checked_add(coefficient).ok_or(ParseError::Overflow)?;
+        self.terms.retain(|_, &mut c| c != 0);
+        Ok(())
+    }
 
-                if (sum % 10) != (rhs_sum % 10) as u8 {
-                    return false; ///////   This is what we looking for to eliminate unwanted permutation
-                }
+    fn add(mut self, other: &Self) -> Result<Self, ParseError> {
+        for (monomial, &coefficient) in &other.terms {
+            self.add_term(monomial.clone(), coefficient)?;
+        }
+        Ok(self)
+    }
 
-                shift = rhs_sum / 10;
-            } else {
-                return true;
-            }
+    fn sub(mut self, other: &Self) -> Result<Self, ParseError> {
+        for (monomial, &coefficient) in &other.terms {
+            self.add_term(monomial.clone(), -coefficient)?;
         }
+        Ok(self)
+    }
 
-        return true;
+    fn mul(&self, other: &Self) -> Result<Self, ParseError> {
+        let mut product = Polynomial::default();
+        for (a, &x) in &self.terms {
+            for (b, &y) in &other.terms {
+                let mut monomial = a.clone();
+                monomial.extend(b);
+                monomial.sort_unstable();
+                let coefficient = x.checked_mul(y).ok_or(ParseError::Overflow)?;
+                product.add_term(monomial, coefficient)?;
+            }
+        }
+        Ok(product)
     }
 
-    fn test_solution(&self, solution: &HashMap<char, u8>) -> bool {
-        self.sum.value(solution) == self.summands.value(solution)
+    /// Largest possible absolute value of the polynomial,
+    /// `None` if it does not fit into `i128`.
+    fn bound(&self, radix: u8) -> Option<i128> {
+        self.terms.iter().try_fold(0i128, |sum, (monomial, &c)| {
+            let max = (i128::from(radix) - 1).checked_pow(monomial.len() as u32)?;
+            sum.checked_add(c.checked_abs()?.checked_mul(max)?)
+        })
     }
 }
 
+/// Polynomial over letter indices which must be zero, or divisible by
+/// `modulus` if it only covers the lowest digits of an equation.
 #[derive(Clone, Debug)]
-struct AlphameticNumber {
-    // can be single number or sum of numbers
-    // ABC ->
-    //      letters: [C, B, A]
-    letters: Vec<char>,
+struct Check {
+    terms: Vec<(i128, Vec<usize>)>,
+    modulus: Option<i128>,
 }
 
-impl AlphameticNumber {
-    fn from(letters: Vec<char>) -> Self {
-        AlphameticNumber { letters }
+impl Check {
+    /// Checks of an equation by the number of letters they need: modulo
+    /// radix, radix^2, ... as the lowest digits of all numbers become known,
+    /// and the exact check when all letters are assigned.
+    fn all(terms: Vec<(i128, Vec<usize>)>, radix: u8) -> BTreeMap<usize, Check> {
+        let depth = |terms: &[(i128, Vec<usize>)]| {
+            terms
+                .iter()
+                .flat_map(|(_, monomial)| monomial.iter().map(|idx| idx + 1))
+                .max()
+                .unwrap_or(0)
+        };
+
+        let mut checks = BTreeMap::new();
+        let max = terms.iter().map(|(c, _)| c.abs()).max().unwrap_or(0);
+        let mut modulus = i128::from(radix);
+        while modulus <= max {
+            // lower digits of a product only depend on lower digits of factors
+            let reduced: Vec<_> = terms
+                .iter()
+                .map(|(c, monomial)| (c % modulus, monomial.clone()))
+                .filter(|&(c, _)| c != 0)
+                .collect();
+            if !reduced.is_empty() {
+                let check = Check {
+                    modulus: Some(modulus),
+                    terms: reduced,
+                };
+                checks.insert(depth(&check.terms), check);
+            }
+            modulus = match modulus.checked_mul(radix.into()) {
+                Some(modulus) => modulus,
+                None => break,
+            };
+        }
+
+        if !terms.is_empty() {
+            checks.insert(
+                depth(&terms),
+                Check {
+                    terms,
+                    modulus: None,
+                },
+            );
+        }
+
+        checks
     }
 
-    fn value(&self, solution: &HashMap<char, u8>) -> u64 {
-        let mut sum = 0;
+    fn holds(&self, digits: &[u8]) -> bool {
+        let value: i128 = self
+            .terms
+            .iter()
+            .map(|(c, monomial)| {
+                c * monomial
+                    .iter()
+                    .map(|&idx| i128::from(digits[idx]))
+                    .product::<i128>()
+            })
+            .sum();
+
+        match self.modulus {
+            Some(modulus) => value % modulus == 0,
+            None => value == 0,
+        }
+    }
+}
 
-        for l in self.letters.iter().rev() {
-            let val = *solution.get(l).unwrap() as u64;
-            sum *= 10;
-            sum += val;
+impl From<ParseError> for PuzzleError {
+    fn from(err: ParseError) -> Self {
+        PuzzleError::Malformed(err)
+    }
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PuzzleError::Malformed(err) => write!(f, "malformed puzzle: {}", err),
+            PuzzleError::MissingEquals { position } => {
+                write!(f, "equation at {} has no `==`", position)
+            }
+            PuzzleError::TooManyLetters { letters, radix } => {
+                write!(f, "{} letters but only {} digits", letters, radix)
+            }
+            PuzzleError::InvalidRadix(radix) => {
+                write!(f, "radix {} is not in the range from 2 to 36", radix)
+            }
         }
+    }
+}
 
-        sum
+impl std::error::Error for PuzzleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PuzzleError::Malformed(err) => Some(err),
+            _ => None,
+        }
     }
 }
 
-#[derive(Clone, Debug)]
-struct AlphameticNumbersSum {
-    // ABC + AED + CBBB ->
-    // [(B->1,C->1,D->1), (B->2,E->1), (A->2,B->1), (C->1)]
-    letters_count: Vec<HashMap<char, u8>>,
-    letters_columns: Vec<Vec<(char, u8)>>,
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "no equations"),
+            ParseError::InvalidCharacter {
+                character,
+                position,
+            } => write!(f, "invalid character {:?} at {}", character, position),
+            ParseError::UnexpectedToken { position } => {
+                write!(f, "unexpected token at {}", position)
+            }
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            ParseError::Overflow => write!(f, "numbers are too large"),
+        }
+    }
 }
 
-impl AlphameticNumbersSum {
-    fn new() -> Self {
-        Self {
-            letters_count: Vec::new(),
-            letters_columns: Vec::new(),
+impl std::error::Error for ParseError {}
+
+mod parser {
+    use super::PuzzleError;
+
+    /// Why the input is not a valid puzzle, positions are byte offsets.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum ParseError {
+        Empty,
+        InvalidCharacter { character: char, position: usize },
+        UnexpectedToken { position: usize },
+        UnexpectedEnd,
+        Overflow,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Expr {
+        Word(Vec<char>),
+        Add(Box<Expr>, Box<Expr>),
+        Sub(Box<Expr>, Box<Expr>),
+        Mul(Box<Expr>, Box<Expr>),
+    }
+
+    impl Expr {
+        pub fn words(&self) -> Box<dyn Iterator<Item = &Vec<char>> + '_> {
+            match self {
+                Expr::Word(letters) => Box::new(std::iter::once(letters)),
+                Expr::Add(lhs, rhs) | Expr::Sub(lhs, rhs) | Expr::Mul(lhs, rhs) => {
+                    Box::new(lhs.words().chain(rhs.words()))
+                }
+            }
         }
     }
 
-    fn convert_to_columns(&mut self) {
-        for lc in &self.letters_count {
-            let mut l_c_vec = Vec::new();
-            l_c_vec.extend(lc.clone());
-            self.letters_columns.push(l_c_vec);
+    #[derive(Clone, Debug, PartialEq)]
+    enum Token {
+        Word(Vec<char>),
+        Plus,
+        Minus,
+        Star,
+        Open,
+        Close,
+        Equals,
+        Separator,
+    }
+
+    fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+        let mut tokens = Vec::new();
+        let mut chars = input.char_indices().peekable();
+        while let Some((position, c)) = chars.next() {
+            let token = match c {
+                '\n' | ',' | ';' => Token::Separator,
+                c if c.is_whitespace() => continue,
+                '+' => Token::Plus,
+                '-' => Token::Minus,
+                '*' => Token::Star,
+                '(' => Token::Open,
+                ')' => Token::Close,
+                '=' if chars.next_if(|&(_, c)| c == '=').is_some() => Token::Equals,
+                c if c.is_alphabetic() => {
+                    let mut word = vec![c];
+                    while let Some((_, c)) = chars.next_if(|&(_, c)| c.is_alphabetic()) {
+                        word.push(c);
+                    }
+                    Token::Word(word)
+                }
+                character => {
+                    return Err(ParseError::InvalidCharacter {
+                        character,
+                        position,
+                    })
+                }
+            };
+            tokens.push((position, token));
         }
+        Ok(tokens)
     }
 
-    fn value(&self, solution: &HashMap<char, u8>) -> u64 {
-        let mut sum = 0;
+    /// Equations as pairs of sides.
+    pub fn parse(input: &str) -> Result<Vec<(Expr, Expr)>, PuzzleError> {
+        let tokens = tokenize(input)?;
+        let mut equations = Vec::new();
+        for equation in tokens.split(|(_, token)| *token == Token::Separator) {
+            if equation.is_empty() {
+                continue;
+            }
 
-        for lc in self.letters_columns.iter().rev() {
-            sum *= 10;
-            sum += lc
-                .iter()
-                .map(|(letter, count)| (*solution.get(letter).unwrap() as u64) * (*count as u64))
-                .sum::<u64>();
+            let mut parser = Parser {
+                tokens: equation,
+                pos: 0,
+            };
+            let lhs = parser.expr()?;
+            match parser.next() {
+                Some((_, Token::Equals)) => {}
+                Some((position, _)) => return Err(ParseError::UnexpectedToken { position }.into()),
+                None => {
+                    return Err(PuzzleError::MissingEquals {
+                        position: equation[0].0,
+                    })
+                }
+            }
+            let rhs = parser.expr()?;
+            if let Some((position, _)) = parser.next() {
+                return Err(ParseError::UnexpectedToken { position }.into());
+            }
+            equations.push((lhs, rhs));
         }
-        sum
+
+        if equations.is_empty() {
+            return Err(ParseError::Empty.into());
+        }
+        Ok(equations)
     }
-}
 
-impl std::ops::AddAssign<AlphameticNumber> for AlphameticNumbersSum {
-    fn add_assign(&mut self, rhs: AlphameticNumber) {
-        if rhs.letters.len() > self.letters_count.len() {
-            self.letters_count.resize(rhs.letters.len(), HashMap::new());
+    // expr   := term (('+' | '-') term)*
+    // term   := factor ('*' factor)*
+    // factor := word | '(' expr ')'
+    struct Parser<'a> {
+        tokens: &'a [(usize, Token)],
+        pos: usize,
+    }
+
+    impl<'a> Parser<'a> {
+        fn peek(&self) -> Option<&'a Token> {
+            self.tokens.get(self.pos).map(|(_, token)| token)
+        }
+
+        fn next(&mut self) -> Option<(usize, &'a Token)> {
+            let (position, token) = self.tokens.get(self.pos)?;
+            self.pos += 1;
+            Some((*position, token))
+        }
+
+        fn expr(&mut self) -> Result<Expr, ParseError> {
+            let mut expr = self.term()?;
+            loop {
+                expr = match self.peek() {
+                    Some(Token::Plus) => {
+                        self.pos += 1;
+                        Expr::Add(Box::new(expr), Box::new(self.term()?))
+                    }
+                    Some(Token::Minus) => {
+                        self.pos += 1;
+                        Expr::Sub(Box::new(expr), Box::new(self.term()?))
+                    }
+                    _ => return Ok(expr),
+                }
+            }
+        }
+
+        fn term(&mut self) -> Result<Expr, ParseError> {
+            let mut expr = self.factor()?;
+            while let Some(Token::Star) = self.peek() {
+                self.pos += 1;
+                expr = Expr::Mul(Box::new(expr), Box::new(self.factor()?));
+            }
+            Ok(expr)
         }
-        for (idx, rhs_letter) in rhs.letters.iter().enumerate() {
-            let entry = self.letters_count[idx].entry(*rhs_letter).or_default();
-            *entry += 1;
+
+        fn factor(&mut self) -> Result<Expr, ParseError> {
+            match self.next() {
+                Some((_, Token::Word(letters))) => Ok(Expr::Word(letters.clone())),
+                Some((_, Token::Open)) => {
+                    let expr = self.expr()?;
+                    match self.next() {
+                        Some((_, Token::Close)) => Ok(expr),
+                        Some((position, _)) => Err(ParseError::UnexpectedToken { position }),
+                        None => Err(ParseError::UnexpectedEnd),
+                    }
+                }
+                Some((position, _)) => Err(ParseError::UnexpectedToken { position }),
+                None => Err(ParseError::UnexpectedEnd),
+            }
         }
     }
 }
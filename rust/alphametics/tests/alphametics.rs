@@ -1,9 +1,10 @@
+use alphametics::{ParseError, PuzzleError};
 use std::collections::HashMap;
 
 fn assert_alphametic_solution_eq(puzzle: &str, solution: &[(char, u8)]) {
-    let answer = alphametics::solve(puzzle);
+    let answer = alphametics::solve(puzzle).map(|answer| answer.map(HashMap::from));
     let solution: HashMap<char, u8> = solution.iter().cloned().collect();
-    assert_eq!(answer, Some(solution));
+    assert_eq!(answer, Ok(Some(solution)));
 }
 
 #[test]
@@ -14,13 +15,13 @@ fn test_with_three_letters() {
 #[test]
 fn test_must_have_unique_value_for_each_letter() {
     let answer = alphametics::solve("A == B");
-    assert_eq!(answer, None);
+    assert_eq!(answer, Ok(None));
 }
 
 #[test]
 fn test_leading_zero_solution_is_invalid() {
     let answer = alphametics::solve("ACA + DD == BD");
-    assert_eq!(answer, None);
+    assert_eq!(answer, Ok(None));
 }
 
 #[test]
@@ -114,3 +115,393 @@ fn test_puzzle_with_ten_letters_and_199_addends() {
         ],
     );
 }
+
+// Expressions and multiple equations
+
+#[test]
+fn test_subtraction() {
+    assert_alphametic_solution_eq(
+        "MONEY - MORE == SEND",
+        &[
+            ('S', 9),
+            ('E', 5),
+            ('N', 6),
+            ('D', 7),
+            ('M', 1),
+            ('O', 0),
+            ('R', 8),
+            ('Y', 2),
+        ],
+    );
+}
+
+#[test]
+fn test_multiplication() {
+    assert_alphametic_solution_eq("AB * AB == CAB", &[('A', 2), ('B', 5), ('C', 6)]);
+}
+
+#[test]
+fn test_parentheses_on_both_sides() {
+    assert_alphametic_solution_eq(
+        "A * (A + B) == (C + B) * (C - B) + A",
+        &[('A', 3), ('B', 2), ('C', 4)],
+    );
+}
+
+#[test]
+fn test_equations_sharing_letters() {
+    assert_alphametic_solution_eq(
+        "AB * C == DB, A + C == B",
+        &[('A', 2), ('B', 5), ('C', 3), ('D', 7)],
+    );
+}
+
+#[test]
+fn test_equations_on_separate_lines() {
+    assert_alphametic_solution_eq(
+        "AB * C == DB\nA + C == B\n",
+        &[('A', 2), ('B', 5), ('C', 3), ('D', 7)],
+    );
+}
+
+#[test]
+fn test_equations_without_common_solution() {
+    assert_eq!(
+        alphametics::solve("A + B == C; B + C == D; C + D == E; D + E == FG"),
+        Ok(None)
+    );
+}
+
+#[test]
+fn test_malformed_puzzle_is_an_error() {
+    assert_eq!(
+        alphametics::solve(""),
+        Err(PuzzleError::Malformed(ParseError::Empty))
+    );
+    assert_eq!(
+        alphametics::solve("A + B"),
+        Err(PuzzleError::MissingEquals { position: 0 })
+    );
+    assert_eq!(
+        alphametics::solve("+ == A"),
+        Err(PuzzleError::Malformed(ParseError::UnexpectedToken {
+            position: 0
+        }))
+    );
+}
+
+#[test]
+fn test_parse_errors() {
+    let error = |input| match alphametics::Puzzle::parse(input) {
+        Err(PuzzleError::Malformed(err)) => err,
+        other => panic!("{:?}", other),
+    };
+    assert_eq!(error(" \n, "), ParseError::Empty);
+    assert_eq!(
+        error("A + == B"),
+        ParseError::UnexpectedToken { position: 4 }
+    );
+    assert_eq!(
+        error("A + 1 == B"),
+        ParseError::InvalidCharacter {
+            character: '1',
+            position: 4
+        }
+    );
+    assert_eq!(
+        error("A = B"),
+        ParseError::InvalidCharacter {
+            character: '=',
+            position: 2
+        }
+    );
+    assert_eq!(
+        error("(A + B == C"),
+        ParseError::UnexpectedToken { position: 7 }
+    );
+    assert_eq!(error("A + B == (C"), ParseError::UnexpectedEnd);
+    assert_eq!(
+        error("A == B == C"),
+        ParseError::UnexpectedToken { position: 7 }
+    );
+    assert_eq!(
+        error(&format!("{} == B", "A".repeat(40))),
+        ParseError::Overflow
+    );
+}
+
+// All solutions
+
+#[test]
+fn test_unique_solution() {
+    assert_eq!(
+        alphametics::count_solutions("SEND + MORE == MONEY", 2),
+        Ok(1)
+    );
+}
+
+#[test]
+fn test_no_solutions() {
+    assert_eq!(alphametics::count_solutions("A == B", 2), Ok(0));
+    assert_eq!(
+        alphametics::solve_all("ACA + DD == BD").unwrap().next(),
+        None
+    );
+}
+
+#[test]
+fn test_count_solutions_stops_at_limit() {
+    assert_eq!(alphametics::count_solutions("A + B == C", 5), Ok(5));
+    assert_eq!(alphametics::count_solutions("A + B == C", 100), Ok(32));
+    assert_eq!(alphametics::count_solutions("A + B == C", 0), Ok(0));
+}
+
+#[test]
+fn test_solve_all() {
+    let mut solutions: Vec<Vec<(char, u8)>> =
+        alphametics::solve_all("A + B == C; B + C == D; C + D == E")
+            .unwrap()
+            .map(|solution| {
+                let mut solution: Vec<_> = HashMap::from(solution).into_iter().collect();
+                solution.sort_unstable();
+                solution
+            })
+            .collect();
+    solutions.sort_unstable();
+    assert_eq!(
+        solutions,
+        vec![
+            vec![('A', 1), ('B', 2), ('C', 3), ('D', 5), ('E', 8)],
+            vec![('A', 2), ('B', 1), ('C', 3), ('D', 4), ('E', 7)],
+            vec![('A', 3), ('B', 1), ('C', 4), ('D', 5), ('E', 9)],
+        ]
+    );
+}
+
+#[test]
+fn test_solve_is_first_of_solve_all() {
+    let puzzle = alphametics::Puzzle::parse("A + B == C").unwrap();
+    assert_eq!(puzzle.solve(), puzzle.solve_all().next());
+}
+
+#[test]
+fn test_all_solutions_are_valid() {
+    for solution in alphametics::solve_all("AB + CD == EF").unwrap().take(50) {
+        let value = |word: &str| {
+            word.chars().fold(0, |value, letter| {
+                value * 10 + u32::from(solution.digits()[&letter])
+            })
+        };
+        assert_eq!(value("AB") + value("CD"), value("EF"));
+    }
+}
+
+#[test]
+fn test_count_solutions_of_malformed_puzzle() {
+    assert_eq!(
+        alphametics::count_solutions("A + B", 1),
+        Err(PuzzleError::MissingEquals { position: 0 })
+    );
+}
+
+// Parallel search
+
+fn assert_parallel_same_as_sequential(puzzle: &str) {
+    let puzzle = alphametics::Puzzle::parse(puzzle).unwrap();
+    let expected = puzzle.solve_all().next();
+    for &workers in &[0, 1, 2, 3, 8, 64] {
+        assert_eq!(
+            puzzle.solve_parallel(workers),
+            expected,
+            "{} workers",
+            workers
+        );
+    }
+}
+
+#[test]
+fn test_parallel_unique_solution() {
+    assert_parallel_same_as_sequential("SEND + MORE == MONEY");
+}
+
+#[test]
+fn test_parallel_no_solution() {
+    assert_parallel_same_as_sequential("ACA + DD == BD");
+    assert_parallel_same_as_sequential("A == B");
+}
+
+#[test]
+fn test_parallel_result_is_deterministic() {
+    assert_parallel_same_as_sequential("A + B == C");
+    assert_parallel_same_as_sequential("AB + CD == EF");
+    assert_parallel_same_as_sequential("A + B == C; B + C == D; C + D == E");
+}
+
+#[test]
+fn test_parallel_single_letter() {
+    assert_parallel_same_as_sequential("A * A == A");
+}
+
+#[test]
+fn test_parallel_ten_letters() {
+    let puzzle =
+        alphametics::Puzzle::parse("AND + A + STRONG + OFFENSE + AS + A + GOOD == DEFENSE")
+            .unwrap();
+    let solution = puzzle.solve_parallel(4).unwrap();
+    assert_eq!(solution.digit('A'), Some(5));
+    assert_eq!(solution.digit('T'), Some(9));
+}
+
+// Radix and generator
+
+fn value(word: &str, solution: &alphametics::Solution, radix: u64) -> u64 {
+    word.chars().fold(0, |value, letter| {
+        value * radix + u64::from(solution.digit(letter).unwrap())
+    })
+}
+
+#[test]
+fn test_hexadecimal() {
+    let puzzle = alphametics::Puzzle::parse_radix("AA + BB == CBC", 16).unwrap();
+    let solution: HashMap<char, u8> = [('A', 15), ('B', 2), ('C', 1)].iter().cloned().collect();
+    assert_eq!(puzzle.radix(), 16);
+    assert_eq!(puzzle.solve().map(HashMap::from), Some(solution));
+    assert_eq!(puzzle.count_solutions(2), 1);
+}
+
+#[test]
+fn test_hexadecimal_with_more_than_ten_letters() {
+    let puzzle = alphametics::Puzzle::parse_radix("BLACK + WHITE == PIXELS", 16).unwrap();
+    let solution = puzzle.solve().unwrap();
+    assert_eq!(solution.digits().len(), 13);
+    assert_eq!(
+        value("BLACK", &solution, 16) + value("WHITE", &solution, 16),
+        value("PIXELS", &solution, 16)
+    );
+}
+
+#[test]
+fn test_hexadecimal_solutions_are_valid() {
+    let puzzle = alphametics::Puzzle::parse_radix("SEND + MORE == MONEY", 16).unwrap();
+    for solution in puzzle.solve_all() {
+        assert!(solution.digits().values().all(|&digit| digit < 16));
+        assert_eq!(
+            value("SEND", &solution, 16) + value("MORE", &solution, 16),
+            value("MONEY", &solution, 16)
+        );
+    }
+}
+
+#[test]
+fn test_binary() {
+    let puzzle = alphametics::Puzzle::parse_radix("A + A == AB", 2).unwrap();
+    let solution: HashMap<char, u8> = [('A', 1), ('B', 0)].iter().cloned().collect();
+    assert_eq!(puzzle.solve().map(HashMap::from), Some(solution));
+}
+
+#[test]
+fn test_too_many_letters_for_radix() {
+    assert_eq!(
+        alphametics::Puzzle::parse_radix("A + B == CD", 3).unwrap_err(),
+        PuzzleError::TooManyLetters {
+            letters: 4,
+            radix: 3
+        }
+    );
+    assert_eq!(
+        alphametics::solve("ABCDE + FGHIJ == KLMNOP"),
+        Err(PuzzleError::TooManyLetters {
+            letters: 16,
+            radix: 10
+        })
+    );
+}
+
+#[test]
+fn test_invalid_radix() {
+    assert_eq!(
+        alphametics::Puzzle::parse_radix("A == A", 37).unwrap_err(),
+        PuzzleError::InvalidRadix(37)
+    );
+    assert_eq!(
+        alphametics::Puzzle::parse_radix("A == A", 1).unwrap_err(),
+        PuzzleError::InvalidRadix(1)
+    );
+    assert_eq!(
+        alphametics::generate(&["A", "B", "C"], 40).err(),
+        Some(PuzzleError::InvalidRadix(40))
+    );
+}
+
+#[test]
+fn test_generate() {
+    let words = ["SEND", "MORE", "MONEY", "I", "BB", "ILL", "A"];
+    let puzzles: Vec<_> = alphametics::generate(&words, 10).unwrap().collect();
+    assert!(puzzles.contains(&"SEND + MORE == MONEY".to_string()));
+    assert!(puzzles.contains(&"I + BB == ILL".to_string()));
+    for puzzle in &puzzles {
+        assert_eq!(alphametics::count_solutions(puzzle, 2), Ok(1), "{}", puzzle);
+    }
+}
+
+#[test]
+fn test_generate_skips_invalid_words() {
+    let words = ["I", "BB", "ILL", "B2"];
+    assert_eq!(
+        alphametics::generate(&words, 10)
+            .unwrap()
+            .collect::<Vec<_>>(),
+        vec!["I + BB == ILL".to_string()]
+    );
+}
+
+#[test]
+fn test_generate_in_other_radix() {
+    let words = ["AA", "BB", "CBC"];
+    assert_eq!(
+        alphametics::generate(&words, 16)
+            .unwrap()
+            .collect::<Vec<_>>(),
+        vec!["AA + BB == CBC".to_string()]
+    );
+}
+
+// Rendering
+
+#[test]
+fn test_render_solution() {
+    let solution = alphametics::solve("SEND + MORE == MONEY").unwrap().unwrap();
+    assert_eq!(solution.to_string(), "9567 + 1085 == 10652");
+}
+
+#[test]
+fn test_render_keeps_layout() {
+    let solution = alphametics::solve("AB * C == DB\n(A + C) == B")
+        .unwrap()
+        .unwrap();
+    assert_eq!(solution.to_string(), "25 * 3 == 75\n(2 + 3) == 5");
+}
+
+#[test]
+fn test_render_hexadecimal_digits() {
+    let puzzle = alphametics::Puzzle::parse_radix("AA + BB == CBC", 16).unwrap();
+    assert_eq!(puzzle.solve().unwrap().to_string(), "FF + 22 == 121");
+}
+
+#[test]
+fn test_error_display() {
+    assert_eq!(
+        alphametics::solve("A + B").unwrap_err().to_string(),
+        "equation at 0 has no `==`"
+    );
+    assert_eq!(
+        alphametics::solve("A + 1 == B").unwrap_err().to_string(),
+        "malformed puzzle: invalid character '1' at 4"
+    );
+    assert_eq!(
+        alphametics::Puzzle::parse_radix("A + B == CD", 3)
+            .unwrap_err()
+            .to_string(),
+        "4 letters but only 3 digits"
+    );
+}
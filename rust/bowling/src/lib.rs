@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Debug, PartialEq)]
 pub enum Error {
     NotEnoughPinsLeft,
@@ -13,6 +15,28 @@ pub struct BowlingGame {
     current_frame: usize, // 1..10
     pins_left: u16,
     frames: [Frame; FRAMES_NUMBER],
+    rolls: [Vec<u16>; FRAMES_NUMBER],
+}
+
+/// Frame as shown on a scoreboard, see `BowlingGame::frames`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreFrame {
+    /// 1..10
+    pub number: usize,
+    /// Knocked down pins, including bonus rolls of the last frame.
+    pub rolls: Vec<u16>,
+    /// Rolls in standard notation: `X` strike, `/` spare, `-` miss.
+    pub marks: String,
+    /// Score of the game up to this frame once all its bonuses are known.
+    pub total: Option<u16>,
+    /// Next roll goes to this frame.
+    pub is_current: bool,
+}
+
+impl Default for BowlingGame {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BowlingGame {
@@ -21,6 +45,7 @@ impl BowlingGame {
             current_frame: 1,
             pins_left: 10,
             frames: [Frame::NoRolls; 10],
+            rolls: Default::default(),
         }
     }
 
@@ -42,6 +67,7 @@ impl BowlingGame {
         for frame in self.frames[..self.current_frame].iter_mut() {
             frame.update_with_roll(pins);
         }
+        self.rolls[self.current_frame - 1].push(pins);
 
         if self.frames[self.current_frame - 1].is_finished() {
             // last frame?
@@ -63,6 +89,89 @@ impl BowlingGame {
     pub fn score(&self) -> Option<u16> {
         self.frames.iter().rev().map(|f| f.score()).sum()
     }
+
+    /// All frames of the game, including the ones not yet rolled.
+    pub fn frames(&self) -> impl Iterator<Item = ScoreFrame> + '_ {
+        let current_frame = if self.is_complete() {
+            None
+        } else {
+            Some(self.current_frame)
+        };
+
+        self.frames.iter().zip(self.rolls.iter()).enumerate().scan(
+            Some(0),
+            move |total, (idx, (frame, rolls))| {
+                *total = total.and_then(|total| Some(total + frame.score()?));
+                Some(ScoreFrame {
+                    number: idx + 1,
+                    rolls: rolls.clone(),
+                    marks: marks(rolls),
+                    total: *total,
+                    is_current: current_frame == Some(idx + 1),
+                })
+            },
+        )
+    }
+}
+
+fn marks(rolls: &[u16]) -> String {
+    let mut marks = String::with_capacity(rolls.len());
+    let mut pins_left = 10;
+    let mut first_ball = true;
+    for &pins in rolls {
+        marks.push(match pins {
+            10 if first_ball => 'X',
+            x if !first_ball && x == pins_left => '/',
+            0 => '-',
+            x => std::char::from_digit(x.into(), 10).unwrap(),
+        });
+
+        // pins are renewed after two balls or when all are down
+        if first_ball && pins < 10 {
+            pins_left = 10 - pins;
+            first_ball = false;
+        } else {
+            pins_left = 10;
+            first_ball = true;
+        }
+    }
+    marks
+}
+
+/// Scorecard with frame numbers, marks and running totals,
+/// the current frame number is in brackets:
+///
+/// ```text
+/// |  1  |  2  | [3] |  4  | ...
+/// |  X  | 7/  |  9  |     | ...
+/// | 20  | 39  |     |     | ...
+/// ```
+impl fmt::Display for BowlingGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let frames: Vec<_> = self.frames().collect();
+        let width = |frame: &ScoreFrame| if frame.number == FRAMES_NUMBER { 7 } else { 5 };
+
+        for frame in &frames {
+            let number = if frame.is_current {
+                format!("[{}]", frame.number)
+            } else {
+                frame.number.to_string()
+            };
+            write!(f, "|{:^width$}", number, width = width(frame))?;
+        }
+        writeln!(f, "|")?;
+
+        for frame in &frames {
+            write!(f, "|{:^width$}", frame.marks, width = width(frame))?;
+        }
+        writeln!(f, "|")?;
+
+        for frame in &frames {
+            let total = frame.total.map(|t| t.to_string()).unwrap_or_default();
+            write!(f, "|{:^width$}", total, width = width(frame))?;
+        }
+        write!(f, "|")
+    }
 }
 
 use frame::Frame;
@@ -84,10 +193,7 @@ mod frame {
 
     impl Frame {
         pub fn is_finished(&self) -> bool {
-            match self {
-                NoRolls | SingleRoll(_) => false,
-                _ => true,
-            }
+            !matches!(self, NoRolls | SingleRoll(_))
         }
 
         pub fn score(&self) -> Option<u16> {
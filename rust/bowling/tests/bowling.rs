@@ -416,3 +416,122 @@ fn last_two_strikes_followed_by_only_last_bonus_with_non_strike_points() {
 
     assert_eq!(game.score(), Some(31));
 }
+
+fn game_with_rolls(rolls: &[u16]) -> BowlingGame {
+    let mut game = BowlingGame::new();
+    for &pins in rolls {
+        game.roll(pins).unwrap();
+    }
+    game
+}
+
+fn marks(game: &BowlingGame) -> Vec<String> {
+    game.frames().map(|frame| frame.marks).collect()
+}
+
+fn totals(game: &BowlingGame) -> Vec<Option<u16>> {
+    game.frames().map(|frame| frame.total).collect()
+}
+
+#[test]
+fn frames_of_a_new_game() {
+    let game = BowlingGame::new();
+    let frames: Vec<_> = game.frames().collect();
+
+    assert_eq!(frames.len(), 10);
+    assert_eq!(frames[0].number, 1);
+    assert!(frames[0].is_current);
+    assert!(frames.iter().all(|frame| frame.rolls.is_empty()));
+    assert!(frames.iter().all(|frame| frame.total.is_none()));
+}
+
+#[test]
+fn frames_show_strikes_spares_misses_and_digits() {
+    let game = game_with_rolls(&[10, 7, 3, 9, 0, 0, 8, 0, 10]);
+
+    assert_eq!(&marks(&game)[..6], &["X", "7/", "9-", "-8", "-/", ""]);
+    assert_eq!(game.frames().nth(1).unwrap().rolls, vec![7, 3]);
+}
+
+#[test]
+fn running_totals_wait_for_bonus_rolls() {
+    let game = game_with_rolls(&[10, 7, 3, 9, 0, 10]);
+
+    assert_eq!(
+        &totals(&game)[..5],
+        &[Some(20), Some(39), Some(48), None, None]
+    );
+}
+
+#[test]
+fn current_frame_moves_after_the_frame_is_finished() {
+    let current = |game: &BowlingGame| game.frames().position(|frame| frame.is_current);
+
+    let mut game = BowlingGame::new();
+    game.roll(3).unwrap();
+    assert_eq!(current(&game), Some(0));
+    game.roll(4).unwrap();
+    assert_eq!(current(&game), Some(1));
+    game.roll(10).unwrap();
+    assert_eq!(current(&game), Some(2));
+}
+
+#[test]
+fn last_frame_marks_include_bonus_rolls() {
+    let mut rolls = vec![0; 18];
+    rolls.extend(&[10, 7, 3]);
+    let game = game_with_rolls(&rolls);
+    assert_eq!(marks(&game)[9], "X7/");
+
+    let game = game_with_rolls(&[10; 12]);
+    assert_eq!(marks(&game)[9], "XXX");
+
+    let mut rolls = vec![0; 18];
+    rolls.extend(&[6, 4, 10]);
+    let game = game_with_rolls(&rolls);
+    assert_eq!(marks(&game)[9], "6/X");
+}
+
+#[test]
+fn last_frame_is_current_until_bonus_rolls_are_taken() {
+    let mut rolls = vec![0; 18];
+    rolls.extend(&[10, 10]);
+    let mut game = game_with_rolls(&rolls);
+    assert!(game.frames().last().unwrap().is_current);
+
+    game.roll(10).unwrap();
+    assert!(game.frames().all(|frame| !frame.is_current));
+}
+
+#[test]
+fn perfect_game_totals() {
+    let game = game_with_rolls(&[10; 12]);
+
+    assert_eq!(
+        totals(&game),
+        (1..=10).map(|n| Some(n * 30)).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn scorecard() {
+    let game = game_with_rolls(&[10, 7, 3, 9, 0, 10, 0, 8]);
+
+    assert_eq!(
+        game.to_string(),
+        "\
+|  1  |  2  |  3  |  4  |  5  | [6] |  7  |  8  |  9  |  10   |
+|  X  | 7/  | 9-  |  X  | -8  |     |     |     |     |       |
+| 20  | 39  | 48  | 66  | 74  |     |     |     |     |       |"
+    );
+}
+
+#[test]
+fn scorecard_of_a_complete_game() {
+    let game = game_with_rolls(&[10; 12]);
+
+    assert_eq!(
+        game.to_string().lines().last(),
+        Some("| 30  | 60  | 90  | 120 | 150 | 180 | 210 | 240 | 270 |  300  |")
+    );
+}
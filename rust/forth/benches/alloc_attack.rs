@@ -0,0 +1,154 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use forth::Forth;
+
+const WORDS: &str = "abcdefghijklmnopqrstuvwxyz";
+
+// `: a 0 drop ; : b a a ; : c b b ; ...` as in tests/alloc-attack.rs
+fn definitions(depth: usize) -> Vec<String> {
+    let names: Vec<char> = WORDS.chars().take(depth).collect();
+    let mut definitions = vec![": a 0 drop ;".to_string()];
+    for w in names.windows(2) {
+        definitions.push(format!(": {1} {0} {0} ;", w[0], w[1]));
+    }
+    definitions
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("alloc_attack");
+    for depth in [8, 12, 16].iter() {
+        let last_word = WORDS.chars().nth(depth - 1).unwrap().to_string();
+
+        let mut f = Forth::new();
+        let mut lazy = lazy::Forth::new();
+        for definition in definitions(*depth) {
+            f.eval(&definition).unwrap();
+            lazy.eval(&definition);
+        }
+
+        group.bench_with_input(
+            BenchmarkId::new("threaded vm", depth),
+            &last_word,
+            |b, w| b.iter(|| f.eval(w).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("lazy expansion", depth),
+            &last_word,
+            |b, w| b.iter(|| lazy.eval(w)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
+
+/// Previous approach: definitions are kept as tokens and user words are
+/// expanded at run time by a chain of boxed iterators. Only the subset of
+/// words used by the benchmark is supported.
+mod lazy {
+    #[derive(Debug)]
+    enum Token {
+        Value(i32),
+        Drop,
+        Word(String),
+    }
+
+    struct WordDef {
+        name: String,
+        def: Vec<Token>,
+    }
+
+    pub struct Forth {
+        words: Vec<WordDef>,
+        stack: Vec<i32>,
+    }
+
+    impl Forth {
+        pub fn new() -> Self {
+            Self {
+                words: vec![WordDef {
+                    name: "drop".to_string(),
+                    def: vec![Token::Drop],
+                }],
+                stack: Vec::new(),
+            }
+        }
+
+        pub fn eval(&mut self, input: &str) {
+            let mut tokens = input.split_ascii_whitespace().map(|s| {
+                if let Ok(val) = s.parse() {
+                    Token::Value(val)
+                } else {
+                    Token::Word(s.to_string())
+                }
+            });
+            while let Some(token) = tokens.next() {
+                match token {
+                    Token::Word(word) if word == ":" => {
+                        let name = match tokens.next() {
+                            Some(Token::Word(name)) => name,
+                            _ => unreachable!(),
+                        };
+                        let def = tokens
+                            .by_ref()
+                            .take_while(|t| !matches!(t, Token::Word(w) if w == ";"))
+                            .collect();
+                        self.words.push(WordDef { name, def });
+                    }
+                    Token::Word(word) => {
+                        for token in WordTokensIterator::new(&word, &self.words) {
+                            match token {
+                                Token::Value(val) => self.stack.push(*val),
+                                Token::Drop => {
+                                    self.stack.pop().unwrap();
+                                }
+                                Token::Word(_) => unreachable!(),
+                            }
+                        }
+                    }
+                    Token::Value(val) => self.stack.push(val),
+                    Token::Drop => unreachable!(),
+                }
+            }
+        }
+    }
+
+    struct WordTokensIterator<'a> {
+        env: &'a [WordDef],
+        tokens: core::slice::Iter<'a, Token>,
+        next_word_iterator: Option<Box<WordTokensIterator<'a>>>,
+    }
+
+    impl<'a> WordTokensIterator<'a> {
+        fn new(word: &'_ str, env: &'a [WordDef]) -> Self {
+            let word_pos = env.iter().rposition(|w| w.name == word).unwrap();
+            Self {
+                env: &env[..word_pos],
+                tokens: env[word_pos].def.iter(),
+                next_word_iterator: None,
+            }
+        }
+    }
+
+    impl<'a> Iterator for WordTokensIterator<'a> {
+        type Item = &'a Token;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                if let Some(next_word_iterator) = self.next_word_iterator.as_mut() {
+                    if let Some(token) = next_word_iterator.next() {
+                        return Some(token);
+                    }
+                    self.next_word_iterator = None;
+                }
+                match self.tokens.next()? {
+                    Token::Word(word) => {
+                        self.next_word_iterator =
+                            Some(Box::new(WordTokensIterator::new(word, self.env)))
+                    }
+                    token => return Some(token),
+                }
+            }
+        }
+    }
+}
@@ -1,218 +1,1185 @@
+use std::fmt;
+use std::io::{self, Write};
+
+/// Default cell type.
 pub type Value = i32;
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Debug, PartialEq)]
-pub enum Error {
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ErrorKind {
     DivisionByZero,
     StackUnderflow,
     UnknownWord,
     InvalidWord,
+    InvalidAddress,
+    Overflow,
+    OutputFailed,
+    StepLimitExceeded,
+    StackLimitExceeded,
+    NestingLimitExceeded,
+    MemoryLimitExceeded,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            ErrorKind::DivisionByZero => "division by zero",
+            ErrorKind::StackUnderflow => "stack underflow",
+            ErrorKind::UnknownWord => "unknown word",
+            ErrorKind::InvalidWord => "invalid word",
+            ErrorKind::InvalidAddress => "invalid memory address",
+            ErrorKind::Overflow => "arithmetic overflow",
+            ErrorKind::OutputFailed => "failed to write output",
+            ErrorKind::StepLimitExceeded => "step limit exceeded",
+            ErrorKind::StackLimitExceeded => "stack depth limit exceeded",
+            ErrorKind::NestingLimitExceeded => "word nesting limit exceeded",
+            ErrorKind::MemoryLimitExceeded => "memory limit exceeded",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// Error with the location of the token which caused it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Option<usize>,
+    line: Option<usize>,
+    column: Option<usize>,
+    word: Option<String>,
+    trace: Vec<String>,
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Byte offset of the offending token in the `eval` input.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// Line of the offending token, starting from 1.
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Column of the offending token in characters, starting from 1.
+    pub fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    /// The offending token as it was written in the input.
+    pub fn word(&self) -> Option<&str> {
+        self.word.as_deref()
+    }
+
+    /// User defined words which were executing when the error occurred,
+    /// outermost first. Empty if the error did not happen inside of one.
+    pub fn trace(&self) -> &[String] {
+        &self.trace
+    }
+
+    // attach token location unless error already has more precise one
+    fn located(mut self, span: Span<'_>) -> Self {
+        if self.position.is_none() {
+            self.position = Some(span.pos);
+            self.line = Some(span.line);
+            self.column = Some(span.column);
+            self.word = Some(span.text.to_string());
+        }
+        self
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            position: None,
+            line: None,
+            column: None,
+            word: None,
+            trace: Vec::new(),
+        }
+    }
 }
 
-#[derive(Debug)]
-pub struct Forth {
-    env: Env,
-    stack: Vec<Value>,
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let (Some(line), Some(column), Some(word)) = (self.line, self.column, &self.word) {
+            write!(f, " at line {}, column {} ({})", line, column, word)?;
+        }
+        if !self.trace.is_empty() {
+            write!(f, " in {}", self.trace.join(" -> "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Resource limits for evaluation of untrusted input, `None` means unlimited.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Limits {
+    /// Instructions executed by a single `eval` call.
+    pub max_steps: Option<u64>,
+    /// Values on the data stack.
+    pub max_stack_depth: Option<usize>,
+    /// User defined words executing one inside another.
+    pub max_nesting: Option<usize>,
+    /// Memory cells allocated by `variable`, `allot` and `,`,
+    /// `None` means `Limits::DEFAULT_MAX_MEMORY`.
+    pub max_memory: Option<usize>,
+}
+
+impl Limits {
+    /// Memory cells available when there is no explicit limit, so that
+    /// `allot` of a huge size fails instead of exhausting host memory.
+    pub const DEFAULT_MAX_MEMORY: usize = 1 << 24;
+
+    fn memory(&self) -> usize {
+        self.max_memory.unwrap_or(Self::DEFAULT_MAX_MEMORY)
+    }
 }
 
-impl Default for Forth {
+/// Saved interpreter state: data stack, dictionary and memory.
+#[derive(Debug, Clone)]
+pub struct Snapshot<C = Value> {
+    env: Env<C>,
+    stack: Vec<C>,
+    memory: Memory<C>,
+}
+
+/// Interpreter with cells of type `C`. `Forth::new()` uses `i32` cells,
+/// other types are available via `Default` and `with_output_cell`, e.g.
+/// `Forth::<i64>::default()`.
+pub struct Forth<'a, C = Value> {
+    env: Env<C>,
+    stack: Vec<C>,
+    memory: Memory<C>,
+    output: Box<dyn Write + Send + 'a>,
+    limits: Limits,
+    arithmetic: Arithmetic,
+    // instructions executed by the current eval() call
+    steps: u64,
+}
+
+// interpreter can be moved to another thread, f.e. a worker of a service
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    assert_send::<Forth<'static>>();
+};
+
+impl<C: Cell> fmt::Debug for Forth<'_, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Forth")
+            .field("env", &self.env)
+            .field("stack", &self.stack)
+            .field("memory", &self.memory)
+            .field("limits", &self.limits)
+            .field("arithmetic", &self.arithmetic)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Interpreter which prints to stdout and uses checked arithmetic.
+impl<C: Cell> Default for Forth<'_, C> {
     fn default() -> Self {
-        Self::new()
+        Self::with_boxed_output(Box::new(io::stdout()))
     }
 }
 
-impl Forth {
-    pub fn new() -> Forth {
+impl<'a> Forth<'a> {
+    /// Interpreter which prints to stdout.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Interpreter which sends output of `.`, `emit` and friends to the given sink.
+    pub fn with_output(output: impl Write + Send + 'a) -> Self {
+        Self::with_output_cell(output)
+    }
+}
+
+impl<'a, C: Cell> Forth<'a, C> {
+    /// Same as `with_output` for cells of type `C`,
+    /// e.g. `Forth::<i64>::with_output_cell(sink)`.
+    pub fn with_output_cell(output: impl Write + Send + 'a) -> Self {
+        Self::with_boxed_output(Box::new(output))
+    }
+
+    fn with_boxed_output(output: Box<dyn Write + Send + 'a>) -> Self {
         let mut env = Env::new();
-        env.add_word("+".to_string(), vec![Token::Plus]).ok();
-        env.add_word("-".to_string(), vec![Token::Minus]).ok();
-        env.add_word("*".to_string(), vec![Token::Mul]).ok();
-        env.add_word("/".to_string(), vec![Token::Div]).ok();
-        env.add_word("dup".to_string(), vec![Token::Dup]).ok();
-        env.add_word("drop".to_string(), vec![Token::Drop]).ok();
-        env.add_word("swap".to_string(), vec![Token::Swap]).ok();
-        env.add_word("over".to_string(), vec![Token::Over]).ok();
+        env.add_primitive("+", Instr::Plus);
+        env.add_primitive("-", Instr::Minus);
+        env.add_primitive("*", Instr::Mul);
+        env.add_primitive("/", Instr::Div);
+        env.add_primitive("dup", Instr::Dup);
+        env.add_primitive("drop", Instr::Drop);
+        env.add_primitive("swap", Instr::Swap);
+        env.add_primitive("over", Instr::Over);
+        env.add_primitive("=", Instr::Eq);
+        env.add_primitive("<>", Instr::Ne);
+        env.add_primitive("<", Instr::Lt);
+        env.add_primitive(">", Instr::Gt);
+        env.add_primitive("0=", Instr::ZeroEq);
+        env.add_primitive("and", Instr::And);
+        env.add_primitive("or", Instr::Or);
+        env.add_primitive("xor", Instr::Xor);
+        env.add_primitive("invert", Instr::Invert);
+        env.add_primitive("mod", Instr::Mod);
+        env.add_primitive("/mod", Instr::DivMod);
+        env.add_primitive("negate", Instr::Negate);
+        env.add_primitive("abs", Instr::Abs);
+        env.add_primitive("min", Instr::Min);
+        env.add_primitive("max", Instr::Max);
+        env.add_primitive("rot", Instr::Rot);
+        env.add_primitive("-rot", Instr::MinusRot);
+        env.add_primitive("nip", Instr::Nip);
+        env.add_primitive("tuck", Instr::Tuck);
+        env.add_primitive("2dup", Instr::TwoDup);
+        env.add_primitive("2drop", Instr::TwoDrop);
+        env.add_primitive("2swap", Instr::TwoSwap);
+        env.add_primitive("pick", Instr::Pick);
+        env.add_primitive("roll", Instr::Roll);
+        env.add_primitive("!", Instr::Store);
+        env.add_primitive("@", Instr::Fetch);
+        env.add_primitive("+!", Instr::PlusStore);
+        env.add_primitive("allot", Instr::Allot);
+        env.add_primitive("here", Instr::Here);
+        env.add_primitive(",", Instr::Comma);
+        env.add_primitive(".", Instr::Dot);
+        env.add_primitive("emit", Instr::Emit);
+        env.add_primitive("cr", Instr::Cr);
+        env.add_primitive(".s", Instr::DotS);
+        env.add_primitive("words", Instr::Words);
+        env.add_primitive("i", Instr::I);
+        env.add_primitive("j", Instr::J);
+        env.add_primitive("type", Instr::Type);
+        env.add_primitive("base", Instr::Lit(Memory::<C>::base_address()));
+        env.add_primitive("decimal", Instr::Decimal);
+        env.add_primitive("hex", Instr::Hex);
 
         Self {
             env,
             stack: Vec::new(),
+            memory: Memory::new(),
+            output,
+            limits: Limits::default(),
+            arithmetic: Arithmetic::default(),
+            steps: 0,
         }
     }
 
-    pub fn stack(&self) -> &[Value] {
+    pub fn stack(&self) -> &[C] {
         &self.stack
     }
 
+    /// Number in the current base, the way `.` prints it.
+    pub fn format(&self, x: C) -> String {
+        self.memory.format(x)
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Limits apply to subsequent `eval` calls. Exceeding a limit aborts
+    /// the call, but the interpreter stays usable.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn arithmetic(&self) -> Arithmetic {
+        self.arithmetic
+    }
+
+    pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+        self.arithmetic = arithmetic;
+    }
+
+    /// Send output of `.`, `emit` and friends to the given sink from now on.
+    pub fn set_output(&mut self, output: impl Write + Send + 'a) {
+        self.output = Box::new(output);
+    }
+
+    /// Add a word implemented in Rust. Like any other word it shadows
+    /// earlier definitions with the same name, can be redefined and
+    /// forgotten, and only definitions compiled afterwards call it.
+    ///
+    /// Fails with `ErrorKind::InvalidWord` if the name could not be
+    /// defined with `:`, f.e. it is empty, a number or `if`.
+    pub fn register_native(
+        &mut self,
+        name: &str,
+        native: impl Fn(&mut Vec<C>) -> Result<()> + Send + Sync + 'static,
+    ) -> Result<()> {
+        // name must be a single word as the tokenizer sees it
+        let mut lexemes = Tokenizer::new(name);
+        let word = next_word(&mut lexemes, &self.memory)
+            .ok()
+            .filter(|word| word.len() == name.len() && lexemes.next().is_none())
+            .ok_or(ErrorKind::InvalidWord)?;
+        self.env.add_native(word, native, self.memory.len());
+        Ok(())
+    }
+
+    /// Names of the words which can be used, most recent first.
+    pub fn words(&self) -> Vec<&str> {
+        self.env.names()
+    }
+
+    /// Source text of the word as `see` would print it.
+    pub fn definition(&self, name: &str) -> Option<String> {
+        let word = self.env.find(&name.to_lowercase()).ok()?;
+        Some(self.env.source(word))
+    }
+
+    pub fn snapshot(&self) -> Snapshot<C> {
+        Snapshot {
+            env: self.env.clone(),
+            stack: self.stack.clone(),
+            memory: self.memory.clone(),
+        }
+    }
+
+    /// Return to the saved state. Output which was already written stays
+    /// written and limits are not affected.
+    pub fn restore(&mut self, snapshot: Snapshot<C>) {
+        self.env = snapshot.env;
+        self.stack = snapshot.stack;
+        self.memory = snapshot.memory;
+    }
+
+    /// Like `eval`, but on error the stack, dictionary and memory are left
+    /// exactly as they were before the call.
+    pub fn eval_atomic(&mut self, input: &str) -> Result<()> {
+        let snapshot = self.snapshot();
+        let result = self.eval(input);
+        if result.is_err() {
+            self.restore(snapshot);
+        }
+        result
+    }
+
     pub fn eval(&mut self, input: &str) -> Result<()> {
-        fn collect_word_definition(
-            tokens: &mut impl Iterator<Item = Token>,
-        ) -> Result<(String, Vec<Token>)> {
-            if let Some(word_token) = tokens.next() {
-                let word = word_token.word()?;
-                let mut definition_tokens = Vec::new();
-                let mut definition_is_malformed = true;
-                for token in tokens {
-                    if token == Token::Semicolon {
-                        definition_is_malformed = false;
-                        break;
-                    }
-                    definition_tokens.push(token);
-                }
-                if definition_is_malformed {
-                    Err(Error::InvalidWord)
-                } else {
-                    Ok((word, definition_tokens))
+        self.steps = 0;
+        let mut lexemes = Tokenizer::new(input);
+
+        while let Some(Lexeme { span, token }) = lexemes.next() {
+            self.eval_token(token, &mut lexemes)
+                .map_err(|e| e.located(span))?;
+        }
+
+        Ok(())
+    }
+
+    fn max_memory(&self) -> usize {
+        self.limits.memory()
+    }
+
+    fn push(&mut self, x: C) -> Result<()> {
+        if self
+            .limits
+            .max_stack_depth
+            .is_some_and(|max| self.stack.len() >= max)
+        {
+            return Err(ErrorKind::StackLimitExceeded.into());
+        }
+        self.stack.push(x);
+        Ok(())
+    }
+
+    // drop the word with everything defined after it and release memory
+    fn forget(&mut self, word: usize) -> Result<()> {
+        let here = self.env.forget(word)?;
+        self.memory.truncate(here);
+        Ok(())
+    }
+
+    fn eval_token<'i>(
+        &mut self,
+        token: Token,
+        lexemes: &mut impl Iterator<Item = Lexeme<'i>>,
+    ) -> Result<()> {
+        fn collect_word_definition<'i, C: Cell>(
+            lexemes: &mut impl Iterator<Item = Lexeme<'i>>,
+            memory: &Memory<C>,
+        ) -> Result<(String, Vec<Lexeme<'i>>)> {
+            let word = next_word(lexemes, memory)?;
+            let mut definition = Vec::new();
+            for lexeme in lexemes {
+                if lexeme.token == Token::Semicolon {
+                    return Ok((word, definition));
                 }
-            } else {
-                Err(Error::InvalidWord)
+                definition.push(lexeme);
+            }
+            // definition is malformed
+            Err(ErrorKind::InvalidWord.into())
+        }
+
+        match token {
+            // word definition started
+            Token::Colon => {
+                let (word, definition) = collect_word_definition(lexemes, &self.memory)?;
+                let max_memory = self.max_memory();
+                self.env
+                    .add_word(word, definition, &mut self.memory, max_memory)?;
+            }
+
+            // allocate a cell and define word which pushes its address
+            Token::Variable => {
+                let word = next_word(lexemes, &self.memory)?;
+                let here = self.memory.len();
+                let addr = self.memory.push(C::ZERO, self.max_memory())?;
+                self.env.add_variable(word, addr, here);
             }
+
+            Token::Constant => {
+                let word = next_word(lexemes, &self.memory)?;
+                let val = self.stack.pop().ok_or(ErrorKind::StackUnderflow)?;
+                self.env.add_constant(word, val, self.memory.len());
+            }
+
+            Token::Marker => {
+                let word = next_word(lexemes, &self.memory)?;
+                self.env.add_marker(word, self.memory.len());
+            }
+
+            Token::Forget => {
+                let word = next_word(lexemes, &self.memory)?;
+                let word = self.env.find(&word)?;
+                self.forget(word)?;
+            }
+
+            Token::See => {
+                let word = next_word(lexemes, &self.memory)?;
+                let word = self.env.find(&word)?;
+                writeln!(self.output, "{}", self.env.source(word))
+                    .map_err(|_| ErrorKind::OutputFailed)?;
+            }
+
+            // closing semicolon is consumed by collect_word_definition(),
+            // so this must be standalone semicolon and this is a error
+            Token::Semicolon => return Err(ErrorKind::InvalidWord.into()),
+
+            // words shadow numbers, f.e. `face` defined before `hex`
+            Token::Word(word) => match self.env.find(&word) {
+                Ok(word) if self.env.is_marker(word) => self.forget(word)?,
+                // run compiled word on the threaded vm
+                Ok(word) => self.execute(word)?,
+                Err(err) => self.push(self.memory.number(&word).ok_or(err)?)?,
+            },
+
+            Token::PrintString(s) => {
+                self.output
+                    .write_all(s.as_bytes())
+                    .map_err(|_| ErrorKind::OutputFailed)?;
+            }
+
+            // store the string in memory and push its address and length
+            Token::StringLiteral(s) => {
+                let (addr, len) = self.memory.string(&s, self.max_memory())?;
+                self.push(addr)?;
+                self.push(len)?;
+            }
+
+            // control flow is only allowed inside of word definitions
+            _ => return Err(ErrorKind::InvalidWord.into()),
         }
 
-        let mut tokens = input.split_ascii_whitespace().map(Token::from_str);
+        Ok(())
+    }
+}
 
-        while let Some(token) = tokens.next() {
-            match token {
-                // word definition started
-                Token::Colon => {
-                    let (word, definition) = collect_word_definition(&mut tokens)?;
-                    self.env.add_word(word, definition)?;
+// numbers can not be redefined
+fn next_word<'i, C: Cell>(
+    lexemes: &mut impl Iterator<Item = Lexeme<'i>>,
+    memory: &Memory<C>,
+) -> Result<String> {
+    let Lexeme { span, token } = lexemes.next().ok_or(ErrorKind::InvalidWord)?;
+    match token {
+        Token::Word(word) if memory.number(&word).is_none() => Ok(word),
+        _ => Err(Error::from(ErrorKind::InvalidWord).located(span)),
+    }
+}
+
+pub use cell::{Arithmetic, Cell};
+mod cell {
+    use std::convert::TryFrom;
+    use std::fmt;
+    use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+    /// What happens when the result of `+`, `*`, `negate` etc.
+    /// does not fit into a cell.
+    #[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+    pub enum Arithmetic {
+        /// Fail with `ErrorKind::Overflow`.
+        #[default]
+        Checked,
+        /// Wrap around at the boundary of the cell type.
+        Wrapping,
+        /// Clamp to the minimum or maximum value of the cell type.
+        Saturating,
+    }
+
+    /// Signed integer which can be used as a stack and memory cell.
+    pub trait Cell:
+        Copy
+        + Ord
+        + fmt::Debug
+        + fmt::Display
+        + BitAnd<Output = Self>
+        + BitOr<Output = Self>
+        + BitXor<Output = Self>
+        + Not<Output = Self>
+        + 'static
+    {
+        const ZERO: Self;
+        const ONE: Self;
+        const MAX: Self;
+        /// All bits set.
+        const TRUE: Self;
+
+        fn from_usize(n: usize) -> Option<Self>;
+        fn to_usize(self) -> Option<usize>;
+        fn to_u32(self) -> Option<u32>;
+
+        // Arithmetic returns `None` only on overflow in `Arithmetic::Checked`
+        // mode. Divisor must not be zero.
+        fn add(self, rhs: Self, mode: Arithmetic) -> Option<Self>;
+        fn sub(self, rhs: Self, mode: Arithmetic) -> Option<Self>;
+        fn mul(self, rhs: Self, mode: Arithmetic) -> Option<Self>;
+        fn div(self, rhs: Self, mode: Arithmetic) -> Option<Self>;
+        fn rem(self, rhs: Self, mode: Arithmetic) -> Option<Self>;
+        fn neg(self, mode: Arithmetic) -> Option<Self>;
+        fn abs(self, mode: Arithmetic) -> Option<Self>;
+    }
+
+    macro_rules! binary {
+        ($name:ident, $checked:ident, $wrapping:ident, $saturating:ident) => {
+            fn $name(self, rhs: Self, mode: Arithmetic) -> Option<Self> {
+                match mode {
+                    Arithmetic::Checked => self.$checked(rhs),
+                    Arithmetic::Wrapping => Some(self.$wrapping(rhs)),
+                    Arithmetic::Saturating => Some(self.$saturating(rhs)),
                 }
+            }
+        };
+    }
 
-                // closing semicolon is consumed by collect_word_definition(),
-                // so this must be standalone semicolon and this is a error
-                Token::Semicolon => return Err(Error::InvalidWord),
+    macro_rules! unary {
+        ($name:ident, $checked:ident, $wrapping:ident, $saturating:ident) => {
+            fn $name(self, mode: Arithmetic) -> Option<Self> {
+                match mode {
+                    Arithmetic::Checked => self.$checked(),
+                    Arithmetic::Wrapping => Some(self.$wrapping()),
+                    Arithmetic::Saturating => Some(self.$saturating()),
+                }
+            }
+        };
+    }
 
-                // unfold word to simple tokens and evaluate each
-                Token::Word(word) => {
-                    for token in self.env.word_tokens_iter(&word)? {
-                        token.eval(&mut self.stack)?
+    macro_rules! impl_cell {
+        ($($t:ty),*) => {
+            $(
+                impl Cell for $t {
+                    const ZERO: Self = 0;
+                    const ONE: Self = 1;
+                    const MAX: Self = <$t>::MAX;
+                    const TRUE: Self = -1;
+
+                    fn from_usize(n: usize) -> Option<Self> {
+                        Self::try_from(n).ok()
+                    }
+
+                    fn to_usize(self) -> Option<usize> {
+                        usize::try_from(self).ok()
+                    }
+
+                    fn to_u32(self) -> Option<u32> {
+                        u32::try_from(self).ok()
                     }
-                }
 
-                // this must be simple token which can be evaluated
-                _ => {
-                    token.eval(&mut self.stack)?;
+                    binary!(add, checked_add, wrapping_add, saturating_add);
+                    binary!(sub, checked_sub, wrapping_sub, saturating_sub);
+                    binary!(mul, checked_mul, wrapping_mul, saturating_mul);
+                    binary!(div, checked_div, wrapping_div, saturating_div);
+                    // MIN % -1 is 0 in any mode, there is nothing to saturate
+                    binary!(rem, checked_rem, wrapping_rem, wrapping_rem);
+                    unary!(neg, checked_neg, wrapping_neg, saturating_neg);
+                    unary!(abs, checked_abs, wrapping_abs, saturating_abs);
                 }
+            )*
+        };
+    }
+
+    impl_cell!(i32, i64, i128);
+}
+
+use memory::Memory;
+mod memory {
+    use super::Arithmetic;
+    use super::Cell;
+    use super::ErrorKind;
+
+    type Result<T> = std::result::Result<T, ErrorKind>;
+
+    /// Data space which grows from address zero, and user variables
+    /// which live at the top of the address space.
+    #[derive(Debug, Clone)]
+    pub(crate) struct Memory<C> {
+        cells: Vec<C>,
+        // radix of number conversion
+        base: C,
+    }
+
+    impl<C: Cell> Memory<C> {
+        pub fn new() -> Self {
+            Memory {
+                cells: Vec::new(),
+                base: C::from_usize(10).unwrap(),
             }
         }
 
-        Ok(())
+        pub fn base_address() -> C {
+            C::MAX
+        }
+
+        pub fn set_base(&mut self, radix: u32) {
+            self.base = C::from_usize(radix as usize).unwrap();
+        }
+
+        pub fn len(&self) -> usize {
+            self.cells.len()
+        }
+
+        pub fn here(&self) -> Result<C> {
+            C::from_usize(self.cells.len()).ok_or(ErrorKind::Overflow)
+        }
+
+        pub fn truncate(&mut self, len: usize) {
+            self.cells.truncate(len);
+        }
+
+        pub fn get(&self, addr: C) -> Result<C> {
+            if addr == Self::base_address() {
+                return Ok(self.base);
+            }
+            addr.to_usize()
+                .and_then(|addr| self.cells.get(addr))
+                .copied()
+                .ok_or(ErrorKind::InvalidAddress)
+        }
+
+        pub fn get_mut(&mut self, addr: C) -> Result<&mut C> {
+            if addr == Self::base_address() {
+                return Ok(&mut self.base);
+            }
+            addr.to_usize()
+                .and_then(move |addr| self.cells.get_mut(addr))
+                .ok_or(ErrorKind::InvalidAddress)
+        }
+
+        /// `len` cells starting at `addr` in the data space.
+        pub fn slice(&self, addr: C, len: C) -> Result<&[C]> {
+            let start = addr.to_usize().ok_or(ErrorKind::InvalidAddress)?;
+            let len = len.to_usize().ok_or(ErrorKind::InvalidAddress)?;
+            start
+                .checked_add(len)
+                .and_then(|end| self.cells.get(start..end))
+                .ok_or(ErrorKind::InvalidAddress)
+        }
+
+        /// Grow or shrink the data space by `n` cells.
+        pub fn allot(&mut self, n: C, max: usize) -> Result<()> {
+            // addresses never wrap around whatever the arithmetic mode
+            let here = self
+                .here()?
+                .add(n, Arithmetic::Checked)
+                .ok_or(ErrorKind::Overflow)?
+                .to_usize()
+                .ok_or(ErrorKind::InvalidAddress)?;
+            if here > max {
+                return Err(ErrorKind::MemoryLimitExceeded);
+            }
+            // explicit limit may still be more than the host can allocate
+            let additional = here.saturating_sub(self.cells.len());
+            self.cells
+                .try_reserve(additional)
+                .map_err(|_| ErrorKind::MemoryLimitExceeded)?;
+            self.cells.resize(here, C::ZERO);
+            Ok(())
+        }
+
+        /// Append a cell to the data space, returns its address.
+        pub fn push(&mut self, x: C, max: usize) -> Result<C> {
+            let addr = self.here()?;
+            if self.cells.len() >= max {
+                return Err(ErrorKind::MemoryLimitExceeded);
+            }
+            self.cells.push(x);
+            Ok(addr)
+        }
+
+        /// Store the string one character per cell,
+        /// returns its address and length.
+        pub fn string(&mut self, s: &str, max: usize) -> Result<(C, C)> {
+            let addr = self.here()?;
+            let len = s.chars().count();
+            if self.cells.len().saturating_add(len) > max {
+                return Err(ErrorKind::MemoryLimitExceeded);
+            }
+            for c in s.chars() {
+                let c = C::from_usize(c as usize).ok_or(ErrorKind::Overflow)?;
+                self.cells.push(c);
+            }
+            Ok((addr, C::from_usize(len).ok_or(ErrorKind::Overflow)?))
+        }
+
+        fn radix(&self) -> Option<u32> {
+            self.base.to_u32().filter(|radix| (2..=36).contains(radix))
+        }
+
+        /// Parse number in the current base, or in the base given by
+        /// `$` (hexadecimal), `#` (decimal) or `%` (binary) prefix.
+        pub fn number(&self, word: &str) -> Option<C> {
+            let (radix, digits) = match word.as_bytes().first()? {
+                b'$' => (16, &word[1..]),
+                b'#' => (10, &word[1..]),
+                b'%' => (2, &word[1..]),
+                _ => (self.radix()?, word),
+            };
+            let (negative, digits) = match digits.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, digits),
+            };
+            if digits.is_empty() {
+                return None;
+            }
+
+            // accumulate negated value, so that the minimum is representable
+            let base = C::from_usize(radix as usize)?;
+            let mut n = C::ZERO;
+            for c in digits.chars() {
+                let digit = C::from_usize(c.to_digit(radix)? as usize)?;
+                n = n
+                    .mul(base, Arithmetic::Checked)?
+                    .sub(digit, Arithmetic::Checked)?;
+            }
+            if negative {
+                Some(n)
+            } else {
+                n.neg(Arithmetic::Checked)
+            }
+        }
+
+        /// Format number in the current base, decimal if the base is invalid.
+        pub fn format(&self, x: C) -> String {
+            let radix = self.radix().unwrap_or(10);
+            if radix == 10 {
+                return x.to_string();
+            }
+
+            // wrapping division and remainder never fail, remainder has the
+            // sign of the dividend, so the minimum needs no special care
+            let base = C::from_usize(radix as usize).unwrap();
+            let mut digits = Vec::new();
+            let mut n = x;
+            loop {
+                let digit = n
+                    .rem(base, Arithmetic::Wrapping)
+                    .and_then(|d| d.abs(Arithmetic::Wrapping))
+                    .and_then(C::to_u32)
+                    .and_then(|d| std::char::from_digit(d, radix))
+                    .unwrap();
+                digits.push(digit);
+                n = n.div(base, Arithmetic::Wrapping).unwrap();
+                if n == C::ZERO {
+                    break;
+                }
+            }
+            if x < C::ZERO {
+                digits.push('-');
+            }
+            digits.iter().rev().collect()
+        }
     }
 }
 
 use env::Env;
 mod env {
+    use super::Cell;
     use super::Error;
+    use super::ErrorKind;
+    use super::Instr;
+    use super::Lexeme;
+    use super::Memory;
     use super::Result;
     use super::Token;
+    use std::collections::HashSet;
+    use std::fmt;
+    use std::sync::Arc;
 
-    #[derive(Debug)]
-    struct WordDef {
+    type NativeFn<C> = dyn Fn(&mut Vec<C>) -> Result<()> + Send + Sync;
+
+    /// Word implemented by the host application.
+    #[derive(Clone)]
+    pub(crate) struct Native<C>(Arc<NativeFn<C>>);
+
+    impl<C> fmt::Debug for Native<C> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("Native")
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    enum Kind<C> {
+        Primitive,
+        Native(Native<C>),
+        Constant,
+        Variable,
+        // source text of the body
+        Colon(String),
+        Marker,
+    }
+
+    #[derive(Debug, Clone)]
+    struct WordDef<C> {
         name: String,
-        def: Vec<Token>,
+        code: Vec<Instr<C>>,
+        kind: Kind<C>,
+        // memory size and number of string literals before the word was
+        // defined, forgetting the word releases everything allocated since
+        here: usize,
+        strings: usize,
     }
-    #[derive(Debug)]
-    pub(crate) struct Env {
-        words: Vec<WordDef>,
+
+    impl<C> WordDef<C> {
+        // built-ins and constants are inlined into callers,
+        // user definitions are called so that they show up in traces
+        fn inline(&self) -> bool {
+            matches!(
+                self.kind,
+                Kind::Primitive | Kind::Native(_) | Kind::Constant | Kind::Variable
+            )
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub(crate) struct Env<C> {
+        words: Vec<WordDef<C>>,
+        // string literals referenced by compiled code
+        strings: Vec<String>,
     }
 
-    impl Env {
+    // unresolved control flow constructs
+    enum Mark {
+        If(usize),
+        Else(usize),
+        Begin(usize),
+        While(usize, usize),
+        Do(usize),
+    }
+
+    impl<C: Cell> Env<C> {
         pub fn new() -> Self {
-            Env { words: Vec::new() }
-        }
-
-        pub fn add_word(&mut self, word: String, tokens: Vec<Token>) -> Result<()> {
-            // verify that all word tokens refer to previously defined words
-            if tokens.iter().all(|token| match token {
-                Token::Word(word) => self.words.iter().any(|w| w.name == word.as_str()),
-                _ => true,
-            }) {
-                self.words.push(WordDef {
-                    name: word,
-                    def: tokens,
-                });
-                Ok(())
-            } else {
-                Err(Error::UnknownWord)
+            Env {
+                words: Vec::new(),
+                strings: Vec::new(),
             }
         }
 
-        pub fn word_tokens_iter(&self, word: &str) -> Result<WordTokensIterator> {
-            if self.words.iter().any(|w| w.name == word) {
-                Ok(WordTokensIterator::new(word, &self.words[..]))
-            } else {
-                Err(Error::UnknownWord)
+        fn push(&mut self, name: String, code: Vec<Instr<C>>, kind: Kind<C>, here: usize) {
+            self.words.push(WordDef {
+                name,
+                code,
+                kind,
+                here,
+                strings: self.strings.len(),
+            });
+        }
+
+        pub fn add_primitive(&mut self, word: &str, instr: Instr<C>) {
+            self.push(word.to_string(), vec![instr], Kind::Primitive, 0);
+        }
+
+        pub fn add_native(
+            &mut self,
+            word: String,
+            native: impl Fn(&mut Vec<C>) -> Result<()> + Send + Sync + 'static,
+            here: usize,
+        ) {
+            // the native is called through its own dictionary entry
+            let code = vec![Instr::Native(self.words.len())];
+            self.push(word, code, Kind::Native(Native(Arc::new(native))), here);
+        }
+
+        pub fn add_constant(&mut self, word: String, val: C, here: usize) {
+            self.push(word, vec![Instr::Lit(val)], Kind::Constant, here);
+        }
+
+        /// Word which pushes address of the cell allocated at `here`.
+        pub fn add_variable(&mut self, word: String, addr: C, here: usize) {
+            self.push(word, vec![Instr::Lit(addr)], Kind::Variable, here);
+        }
+
+        pub fn add_marker(&mut self, word: String, here: usize) {
+            self.push(word, Vec::new(), Kind::Marker, here);
+        }
+
+        /// Compile definition to the flat instruction array.
+        /// Other words are referenced by their index in the dictionary,
+        /// so later redefinitions do not affect this word. Numbers are
+        /// converted in the current base and `s"` strings are stored in
+        /// memory at compile time.
+        pub fn add_word(
+            &mut self,
+            word: String,
+            lexemes: Vec<Lexeme<'_>>,
+            memory: &mut Memory<C>,
+            max_memory: usize,
+        ) -> Result<()> {
+            let here = memory.len();
+            let strings = self.strings.len();
+            // words are case insensitive, string literals are kept verbatim
+            let source = lexemes
+                .iter()
+                .map(|lexeme| match lexeme.token {
+                    Token::PrintString(_) | Token::StringLiteral(_) => lexeme.span.text.to_string(),
+                    _ => lexeme.span.text.to_lowercase(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            let code = match self.compile(lexemes, memory, max_memory) {
+                Ok(code) => code,
+                Err(err) => {
+                    memory.truncate(here);
+                    return Err(err);
+                }
+            };
+            self.words.push(WordDef {
+                name: word,
+                code,
+                kind: Kind::Colon(source),
+                here,
+                strings,
+            });
+            Ok(())
+        }
+
+        /// Remove the word and everything defined after it. Returns size
+        /// of the memory at the moment the word was defined.
+        pub fn forget(&mut self, word: usize) -> std::result::Result<usize, ErrorKind> {
+            let def = &self.words[word];
+            if let Kind::Primitive = def.kind {
+                return Err(ErrorKind::InvalidWord);
             }
+            let here = def.here;
+            self.strings.truncate(def.strings);
+            self.words.truncate(word);
+            Ok(here)
         }
-    }
 
-    #[derive(Debug)]
-    pub(crate) struct WordTokensIterator<'a> {
-        env: &'a [WordDef],
-        tokens: core::slice::Iter<'a, Token>,
-        next_word_iterator: Option<Box<WordTokensIterator<'a>>>,
-    }
+        pub fn is_marker(&self, word: usize) -> bool {
+            matches!(self.words[word].kind, Kind::Marker)
+        }
 
-    impl<'a> WordTokensIterator<'a> {
-        fn new(word: &'_ str, env: &'a [WordDef]) -> Self {
-            let word_pos = env.iter().rposition(|w| w.name == word).unwrap();
-            Self {
-                env: &env[..word_pos],
-                tokens: env[word_pos].def.iter(),
-                next_word_iterator: None,
+        pub fn is_colon(&self, word: usize) -> bool {
+            matches!(self.words[word].kind, Kind::Colon(_))
+        }
+
+        /// Visible words, most recent first.
+        pub fn names(&self) -> Vec<&str> {
+            let mut seen = HashSet::new();
+            self.words
+                .iter()
+                .rev()
+                .map(|w| w.name.as_str())
+                .filter(|name| seen.insert(*name))
+                .collect()
+        }
+
+        /// Source text which defines the word. Words referenced by a colon
+        /// definition are the ones which were visible when it was compiled.
+        pub fn source(&self, word: usize) -> String {
+            let def = &self.words[word];
+            match (&def.kind, &def.code[..]) {
+                (Kind::Primitive, _) => format!("primitive {}", def.name),
+                (Kind::Native(_), _) => format!("native {}", def.name),
+                (Kind::Constant, [Instr::Lit(val)]) => format!("{} constant {}", val, def.name),
+                (Kind::Variable, _) => format!("variable {}", def.name),
+                (Kind::Colon(body), _) if body.is_empty() => format!(": {} ;", def.name),
+                (Kind::Colon(body), _) => format!(": {} {} ;", def.name, body),
+                (Kind::Marker, _) => format!("marker {}", def.name),
+                (Kind::Constant, _) => unreachable!(),
             }
         }
 
-        fn next_from_tokens(&mut self) -> Option<&'a Token> {
-            if let Some(token) = self.tokens.next() {
-                match token {
-                    Token::Word(word) => {
-                        self.next_word_iterator =
-                            Some(Box::new(WordTokensIterator::new(word, self.env)));
-                        self.next_from_next_word()
-                    }
-                    _ => Some(token),
-                }
-            } else {
-                None
+        pub fn find(&self, word: &str) -> std::result::Result<usize, ErrorKind> {
+            self.words
+                .iter()
+                .rposition(|w| w.name == word)
+                .ok_or(ErrorKind::UnknownWord)
+        }
+
+        pub fn name(&self, word: usize) -> &str {
+            &self.words[word].name
+        }
+
+        pub fn code(&self, word: usize) -> &[Instr<C>] {
+            &self.words[word].code
+        }
+
+        pub fn call_native(&self, word: usize, stack: &mut Vec<C>) -> Result<()> {
+            match &self.words[word].kind {
+                Kind::Native(Native(native)) => native(stack),
+                _ => unreachable!(),
             }
         }
 
-        fn next_from_next_word(&mut self) -> Option<&'a Token> {
-            if let Some(token) = self.next_word_iterator.as_mut().unwrap().next() {
-                Some(token)
+        pub fn string(&self, index: usize) -> &str {
+            &self.strings[index]
+        }
+
+        fn compile(
+            &mut self,
+            lexemes: Vec<Lexeme<'_>>,
+            memory: &mut Memory<C>,
+            max_memory: usize,
+        ) -> Result<Vec<Instr<C>>> {
+            let mut marks = Vec::new();
+            let mut code = Vec::with_capacity(lexemes.len());
+            for Lexeme { span, token } in lexemes {
+                self.compile_token(token, &mut code, &mut marks, memory, max_memory)
+                    .map_err(|kind| Error::from(kind).located(span))?;
+            }
+
+            if marks.is_empty() {
+                Ok(code)
             } else {
-                self.next_word_iterator = None;
-                self.next_from_tokens()
+                Err(ErrorKind::InvalidWord.into())
             }
         }
-    }
 
-    impl<'a> Iterator for WordTokensIterator<'a> {
-        type Item = &'a Token;
+        fn compile_token(
+            &mut self,
+            token: Token,
+            code: &mut Vec<Instr<C>>,
+            marks: &mut Vec<Mark>,
+            memory: &mut Memory<C>,
+            max_memory: usize,
+        ) -> std::result::Result<(), ErrorKind> {
+            // point forward branch at `orig` to the end of the code
+            fn patch<C>(code: &mut [Instr<C>], orig: usize) {
+                let target = code.len();
+                match &mut code[orig] {
+                    Instr::Branch(t) | Instr::BranchIfZero(t) => *t = target,
+                    _ => unreachable!(),
+                }
+            }
 
-        fn next(&mut self) -> Option<Self::Item> {
-            if self.next_word_iterator.is_some() {
-                self.next_from_next_word()
-            } else {
-                self.next_from_tokens()
+            match token {
+                Token::PrintString(s) => {
+                    code.push(Instr::PrintString(self.strings.len()));
+                    self.strings.push(s);
+                }
+                Token::StringLiteral(s) => {
+                    let (addr, len) = memory.string(&s, max_memory)?;
+                    code.push(Instr::Lit(addr));
+                    code.push(Instr::Lit(len));
+                }
+                Token::Word(word) => {
+                    let word = match self.find(&word) {
+                        Ok(word) => word,
+                        Err(err) => {
+                            code.push(Instr::Lit(memory.number(&word).ok_or(err)?));
+                            return Ok(());
+                        }
+                    };
+                    let def = &self.words[word];
+                    match def.code[..] {
+                        // markers rewind the dictionary, so only run interactively
+                        _ if matches!(def.kind, Kind::Marker) => {
+                            return Err(ErrorKind::InvalidWord)
+                        }
+                        [instr] if def.inline() => code.push(instr),
+                        _ => code.push(Instr::Call(word)),
+                    }
+                }
+                Token::If => {
+                    marks.push(Mark::If(code.len()));
+                    code.push(Instr::BranchIfZero(0));
+                }
+                Token::Else => match marks.pop() {
+                    Some(Mark::If(orig)) => {
+                        marks.push(Mark::Else(code.len()));
+                        code.push(Instr::Branch(0));
+                        patch(code, orig);
+                    }
+                    _ => return Err(ErrorKind::InvalidWord),
+                },
+                Token::Then => match marks.pop() {
+                    Some(Mark::If(orig)) | Some(Mark::Else(orig)) => patch(code, orig),
+                    _ => return Err(ErrorKind::InvalidWord),
+                },
+                Token::Begin => marks.push(Mark::Begin(code.len())),
+                Token::Until => match marks.pop() {
+                    Some(Mark::Begin(dest)) => code.push(Instr::BranchIfZero(dest)),
+                    _ => return Err(ErrorKind::InvalidWord),
+                },
+                Token::While => match marks.pop() {
+                    Some(Mark::Begin(dest)) => {
+                        marks.push(Mark::While(dest, code.len()));
+                        code.push(Instr::BranchIfZero(0));
+                    }
+                    _ => return Err(ErrorKind::InvalidWord),
+                },
+                Token::Repeat => match marks.pop() {
+                    Some(Mark::While(dest, orig)) => {
+                        code.push(Instr::Branch(dest));
+                        patch(code, orig);
+                    }
+                    _ => return Err(ErrorKind::InvalidWord),
+                },
+                Token::Do => {
+                    code.push(Instr::Do);
+                    marks.push(Mark::Do(code.len()));
+                }
+                Token::Loop => match marks.pop() {
+                    Some(Mark::Do(dest)) => code.push(Instr::Loop(dest)),
+                    _ => return Err(ErrorKind::InvalidWord),
+                },
+                // defining words and markers only work outside of definitions
+                Token::Colon
+                | Token::Semicolon
+                | Token::Variable
+                | Token::Constant
+                | Token::See
+                | Token::Forget
+                | Token::Marker => return Err(ErrorKind::InvalidWord),
             }
+
+            Ok(())
         }
     }
 }
 
-use token::Token;
-mod token {
-
+use vm::Instr;
+mod vm {
+    use super::Arithmetic;
+    use super::Cell;
     use super::Error;
-    use super::Result;
-    use super::Value;
-    use Token::*;
+    use super::ErrorKind;
+    use super::Forth;
+    use std::io::Write;
+    use Instr::*;
 
-    #[derive(Debug, PartialEq, Clone)]
-    pub enum Token {
-        Colon,
-        Semicolon,
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    pub enum Instr<C> {
         Plus,
         Minus,
         Mul,
@@ -221,85 +1188,575 @@ mod token {
         Drop,
         Swap,
         Over,
-        ValueT(Value),
-        Word(String),
+        Eq,
+        Ne,
+        Lt,
+        Gt,
+        ZeroEq,
+        And,
+        Or,
+        Xor,
+        Invert,
+        Mod,
+        DivMod,
+        Negate,
+        Abs,
+        Min,
+        Max,
+        Rot,
+        MinusRot,
+        Nip,
+        Tuck,
+        TwoDup,
+        TwoDrop,
+        TwoSwap,
+        Pick,
+        Roll,
+        Store,
+        Fetch,
+        PlusStore,
+        Allot,
+        Here,
+        Comma,
+        Decimal,
+        Hex,
+        Dot,
+        Emit,
+        Type,
+        Cr,
+        DotS,
+        Words,
+        PrintString(usize),
+        I,
+        J,
+        Lit(C),
+        // dictionary index of the native word
+        Native(usize),
+        Call(usize),
+        Branch(usize),
+        BranchIfZero(usize),
+        Do,
+        Loop(usize),
     }
 
-    impl Token {
-        pub fn from_str(s: &str) -> Self {
-            if let Ok(val) = s.parse::<Value>() {
-                ValueT(val)
-            } else {
-                match s {
-                    ":" => Colon,
-                    ";" => Semicolon,
-                    s => Word(s.to_lowercase()),
-                }
-            }
+    type Result<T> = std::result::Result<T, ErrorKind>;
+
+    fn pop<C>(stack: &mut Vec<C>) -> Result<C> {
+        stack.pop().ok_or(ErrorKind::StackUnderflow)
+    }
+
+    fn binary<C>(stack: &mut Vec<C>, op: impl Fn(C, C) -> C) -> Result<()> {
+        let x2 = pop(stack)?;
+        let x1 = pop(stack)?;
+        stack.push(op(x1, x2));
+        Ok(())
+    }
+
+    // binary operation which may overflow
+    fn arithmetic<C>(stack: &mut Vec<C>, op: impl Fn(C, C) -> Option<C>) -> Result<()> {
+        binary_checked(stack, |x1, x2| op(x1, x2).ok_or(ErrorKind::Overflow))
+    }
+
+    // like arithmetic(), but division by zero is reported before overflow
+    // and before the dividend is popped, the same way as `/mod` does
+    fn division<C: Cell>(stack: &mut Vec<C>, op: impl Fn(C, C) -> Option<C>) -> Result<()> {
+        let x2 = pop(stack)?;
+        if x2 == C::ZERO {
+            return Err(ErrorKind::DivisionByZero);
         }
+        let x1 = pop(stack)?;
+        stack.push(op(x1, x2).ok_or(ErrorKind::Overflow)?);
+        Ok(())
+    }
 
-        pub fn word(self) -> Result<String> {
-            match self {
-                Word(word) => Ok(word),
-                _ => Err(Error::InvalidWord),
-            }
+    fn binary_checked<C>(stack: &mut Vec<C>, op: impl Fn(C, C) -> Result<C>) -> Result<()> {
+        let x2 = pop(stack)?;
+        let x1 = pop(stack)?;
+        stack.push(op(x1, x2)?);
+        Ok(())
+    }
+
+    fn unary<C>(stack: &mut Vec<C>, op: impl Fn(C) -> Option<C>) -> Result<()> {
+        let x = pop(stack)?;
+        stack.push(op(x).ok_or(ErrorKind::Overflow)?);
+        Ok(())
+    }
+
+    // forth convention: all bits set is true
+    fn flag<C: Cell>(b: bool) -> C {
+        if b {
+            C::TRUE
+        } else {
+            C::ZERO
         }
+    }
 
-        // eval any token except for Word, Colon, Semicolon
-        pub fn eval(&self, stack: &mut Vec<Value>) -> Result<()> {
-            fn pop(stack: &mut Vec<Value>) -> Result<Value> {
-                stack.pop().ok_or(Error::StackUnderflow)
-            }
-            match self {
-                Plus => {
-                    let x2 = pop(stack)?;
-                    let x1 = pop(stack)?;
-                    stack.push(x1 + x2);
-                }
-                Minus => {
-                    let x2 = pop(stack)?;
-                    let x1 = pop(stack)?;
-                    stack.push(x1 - x2);
-                }
-                Mul => {
-                    let x2 = pop(stack)?;
-                    let x1 = pop(stack)?;
-                    stack.push(x1 * x2);
-                }
-                Div => {
-                    let x2 = pop(stack)?;
-                    if x2 == 0 {
-                        return Err(Error::DivisionByZero);
+    // position of the u-th item below the top of the stack
+    fn nth_from_top<C: Cell>(stack: &[C], u: C) -> Result<usize> {
+        u.to_usize()
+            .filter(|&u| u < stack.len())
+            .map(|u| stack.len() - 1 - u)
+            .ok_or(ErrorKind::StackUnderflow)
+    }
+
+    fn to_char<C: Cell>(c: C) -> char {
+        c.to_u32()
+            .and_then(std::char::from_u32)
+            .unwrap_or(std::char::REPLACEMENT_CHARACTER)
+    }
+
+    fn print(output: &mut dyn Write, args: std::fmt::Arguments<'_>) -> Result<()> {
+        output.write_fmt(args).map_err(|_| ErrorKind::OutputFailed)
+    }
+
+    impl<C: Cell> Forth<'_, C> {
+        /// Run compiled word, errors carry the chain of user defined words
+        /// which were executing.
+        pub(crate) fn execute(&mut self, word: usize) -> super::Result<()> {
+            let mut return_stack = vec![(word, 0)];
+            self.run(&mut return_stack).map_err(|kind| {
+                let mut error = Error::from(kind);
+                // primitives and natives called directly are not a part of the trace
+                error.trace = return_stack
+                    .iter()
+                    .filter(|&&(word, _)| self.env.is_colon(word))
+                    .map(|&(word, _)| self.env.name(word).to_string())
+                    .collect();
+                error
+            })
+        }
+
+        /// Inner interpreter: runs the word on top of the return stack keeping
+        /// return addresses there instead of expanding nested words.
+        fn run(&mut self, return_stack: &mut Vec<(usize, usize)>) -> Result<()> {
+            let Forth {
+                env,
+                stack,
+                memory,
+                output,
+                limits,
+                arithmetic: mode,
+                steps,
+            } = self;
+            let mode = *mode;
+
+            let max_steps = limits.max_steps.unwrap_or(u64::MAX);
+            let max_stack_depth = limits.max_stack_depth.unwrap_or(usize::MAX);
+            let max_nesting = limits.max_nesting.unwrap_or(usize::MAX);
+            let max_memory = limits.memory();
+
+            // (index, limit) pairs of the active do ... loop constructs
+            let mut loops: Vec<(C, C)> = Vec::new();
+
+            // (word, pc) of the running word is on top of the return stack,
+            // its pc is only updated when another word is called
+            let (word, mut pc) = return_stack[return_stack.len() - 1];
+            let mut code = env.code(word);
+
+            loop {
+                let instr = match code.get(pc) {
+                    Some(instr) => *instr,
+                    None => {
+                        return_stack.pop();
+                        match return_stack.last() {
+                            Some(&(word, ret)) => {
+                                code = env.code(word);
+                                pc = ret;
+                                continue;
+                            }
+                            None => return Ok(()),
+                        }
                     }
-                    let x1 = pop(stack)?;
-                    stack.push(x1 / x2);
+                };
+                pc += 1;
+
+                if *steps >= max_steps {
+                    return Err(ErrorKind::StepLimitExceeded);
                 }
-                Dup => {
-                    let x = pop(stack)?;
-                    stack.push(x);
-                    stack.push(x);
+                *steps += 1;
+
+                match instr {
+                    Plus => arithmetic(stack, |x1, x2| x1.add(x2, mode))?,
+                    Minus => arithmetic(stack, |x1, x2| x1.sub(x2, mode))?,
+                    Mul => arithmetic(stack, |x1, x2| x1.mul(x2, mode))?,
+                    Div => division(stack, |x1, x2| x1.div(x2, mode))?,
+                    Dup => {
+                        let x = pop(stack)?;
+                        stack.push(x);
+                        stack.push(x);
+                    }
+                    Drop => {
+                        pop(stack)?;
+                    }
+                    Swap => {
+                        let x2 = pop(stack)?;
+                        let x1 = pop(stack)?;
+                        stack.push(x2);
+                        stack.push(x1);
+                    }
+                    Over => {
+                        let x2 = pop(stack)?;
+                        let x1 = pop(stack)?;
+                        stack.push(x1);
+                        stack.push(x2);
+                        stack.push(x1);
+                    }
+                    Eq => binary(stack, |x1, x2| flag(x1 == x2))?,
+                    Ne => binary(stack, |x1, x2| flag(x1 != x2))?,
+                    Lt => binary(stack, |x1, x2| flag(x1 < x2))?,
+                    Gt => binary(stack, |x1, x2| flag(x1 > x2))?,
+                    ZeroEq => {
+                        let x = pop(stack)?;
+                        stack.push(flag(x == C::ZERO));
+                    }
+                    And => binary(stack, |x1, x2| x1 & x2)?,
+                    Or => binary(stack, |x1, x2| x1 | x2)?,
+                    Xor => binary(stack, |x1, x2| x1 ^ x2)?,
+                    Invert => {
+                        let x = pop(stack)?;
+                        stack.push(!x);
+                    }
+                    Mod => division(stack, |x1, x2| x1.rem(x2, mode))?,
+                    DivMod => {
+                        let x2 = pop(stack)?;
+                        if x2 == C::ZERO {
+                            return Err(ErrorKind::DivisionByZero);
+                        }
+                        let x1 = pop(stack)?;
+                        let rem = x1.rem(x2, mode).ok_or(ErrorKind::Overflow)?;
+                        let quot = x1.div(x2, mode).ok_or(ErrorKind::Overflow)?;
+                        stack.push(rem);
+                        stack.push(quot);
+                    }
+                    Negate => unary(stack, |x| x.neg(mode))?,
+                    Abs => unary(stack, |x| x.abs(mode))?,
+                    Min => binary(stack, |x1, x2| x1.min(x2))?,
+                    Max => binary(stack, |x1, x2| x1.max(x2))?,
+                    Rot => {
+                        let x3 = pop(stack)?;
+                        let x2 = pop(stack)?;
+                        let x1 = pop(stack)?;
+                        stack.push(x2);
+                        stack.push(x3);
+                        stack.push(x1);
+                    }
+                    MinusRot => {
+                        let x3 = pop(stack)?;
+                        let x2 = pop(stack)?;
+                        let x1 = pop(stack)?;
+                        stack.push(x3);
+                        stack.push(x1);
+                        stack.push(x2);
+                    }
+                    Nip => {
+                        let x2 = pop(stack)?;
+                        pop(stack)?;
+                        stack.push(x2);
+                    }
+                    Tuck => {
+                        let x2 = pop(stack)?;
+                        let x1 = pop(stack)?;
+                        stack.push(x2);
+                        stack.push(x1);
+                        stack.push(x2);
+                    }
+                    TwoDup => {
+                        let x2 = pop(stack)?;
+                        let x1 = pop(stack)?;
+                        stack.extend_from_slice(&[x1, x2, x1, x2]);
+                    }
+                    TwoDrop => {
+                        pop(stack)?;
+                        pop(stack)?;
+                    }
+                    TwoSwap => {
+                        let x4 = pop(stack)?;
+                        let x3 = pop(stack)?;
+                        let x2 = pop(stack)?;
+                        let x1 = pop(stack)?;
+                        stack.extend_from_slice(&[x3, x4, x1, x2]);
+                    }
+                    Pick => {
+                        let u = pop(stack)?;
+                        let pos = nth_from_top(stack, u)?;
+                        stack.push(stack[pos]);
+                    }
+                    Roll => {
+                        let u = pop(stack)?;
+                        let pos = nth_from_top(stack, u)?;
+                        let x = stack.remove(pos);
+                        stack.push(x);
+                    }
+                    Store => {
+                        let cell = memory.get_mut(pop(stack)?)?;
+                        *cell = pop(stack)?;
+                    }
+                    Fetch => {
+                        let x = memory.get(pop(stack)?)?;
+                        stack.push(x);
+                    }
+                    PlusStore => {
+                        let cell = memory.get_mut(pop(stack)?)?;
+                        let x = pop(stack)?;
+                        *cell = cell.add(x, mode).ok_or(ErrorKind::Overflow)?;
+                    }
+                    Allot => memory.allot(pop(stack)?, max_memory)?,
+                    Here => stack.push(memory.here()?),
+                    Comma => {
+                        memory.push(pop(stack)?, max_memory)?;
+                    }
+                    Decimal => memory.set_base(10),
+                    Hex => memory.set_base(16),
+                    Dot => print(output, format_args!("{} ", memory.format(pop(stack)?)))?,
+                    Emit => print(output, format_args!("{}", to_char(pop(stack)?)))?,
+                    Type => {
+                        let len = pop(stack)?;
+                        let addr = pop(stack)?;
+                        for &c in memory.slice(addr, len)? {
+                            print(output, format_args!("{}", to_char(c)))?;
+                        }
+                    }
+                    Cr => print(output, format_args!("\n"))?,
+                    DotS => {
+                        print(output, format_args!("<{}> ", stack.len()))?;
+                        for &x in stack.iter() {
+                            print(output, format_args!("{} ", memory.format(x)))?;
+                        }
+                    }
+                    Words => {
+                        for name in env.names() {
+                            print(output, format_args!("{} ", name))?;
+                        }
+                    }
+                    PrintString(index) => print(output, format_args!("{}", env.string(index)))?,
+                    // loop indexes are only available while a loop is running
+                    I => match loops.last() {
+                        Some((index, _)) => stack.push(*index),
+                        None => return Err(ErrorKind::InvalidWord),
+                    },
+                    J => match loops.iter().rev().nth(1) {
+                        Some((index, _)) => stack.push(*index),
+                        None => return Err(ErrorKind::InvalidWord),
+                    },
+                    Lit(val) => stack.push(val),
+                    Native(word) => env.call_native(word, stack).map_err(|e| e.kind())?,
+                    Call(word) => {
+                        if let Some(caller) = return_stack.last_mut() {
+                            caller.1 = pc;
+                        }
+                        if return_stack.len() >= max_nesting {
+                            return Err(ErrorKind::NestingLimitExceeded);
+                        }
+                        return_stack.push((word, 0));
+                        code = env.code(word);
+                        pc = 0;
+                    }
+                    Branch(target) => pc = target,
+                    BranchIfZero(target) => {
+                        if pop(stack)? == C::ZERO {
+                            pc = target;
+                        }
+                    }
+                    Do => {
+                        let index = pop(stack)?;
+                        let limit = pop(stack)?;
+                        loops.push((index, limit));
+                    }
+                    Loop(target) => {
+                        // loops are guaranteed to be balanced by the compiler
+                        let (index, limit) = loops.last_mut().unwrap();
+                        match index.add(C::ONE, Arithmetic::Checked) {
+                            Some(next) if next < *limit => {
+                                *index = next;
+                                pc = target;
+                            }
+                            _ => {
+                                loops.pop();
+                            }
+                        }
+                    }
                 }
-                Drop => {
-                    pop(stack)?;
+
+                // drop what the instruction pushed over the limit, so that
+                // failing calls can not grow the stack any further
+                if stack.len() > max_stack_depth {
+                    stack.truncate(max_stack_depth);
+                    return Err(ErrorKind::StackLimitExceeded);
                 }
-                Swap => {
-                    let x2 = pop(stack)?;
-                    let x1 = pop(stack)?;
-                    stack.push(x2);
-                    stack.push(x1);
+            }
+        }
+    }
+}
+
+use token::{Lexeme, Span, Token, Tokenizer};
+mod token {
+
+    use Token::*;
+
+    #[derive(Debug, PartialEq, Clone)]
+    pub enum Token {
+        Colon,
+        Semicolon,
+        If,
+        Else,
+        Then,
+        Do,
+        Loop,
+        Begin,
+        Until,
+        While,
+        Repeat,
+        Variable,
+        Constant,
+        See,
+        Forget,
+        Marker,
+        PrintString(String),
+        StringLiteral(String),
+        // numbers are words too, they are recognized by the interpreter
+        Word(String),
+    }
+
+    /// Location of a token in the input.
+    #[derive(Debug, Clone, Copy)]
+    pub struct Span<'a> {
+        /// byte offset
+        pub pos: usize,
+        /// starting from 1
+        pub line: usize,
+        /// in characters, starting from 1
+        pub column: usize,
+        pub text: &'a str,
+    }
+
+    #[derive(Debug)]
+    pub struct Lexeme<'a> {
+        pub span: Span<'a>,
+        pub token: Token,
+    }
+
+    /// Splits input on ascii whitespace and skips `( comments )` and
+    /// `\ comments` up to the end of line. Parsing words `."` and `s"`
+    /// take the following text verbatim up to the closing quote.
+    pub struct Tokenizer<'a> {
+        input: &'a str,
+        pos: usize,
+        line: usize,
+        column: usize,
+    }
+
+    impl<'a> Tokenizer<'a> {
+        pub fn new(input: &'a str) -> Self {
+            Self {
+                input,
+                pos: 0,
+                line: 1,
+                column: 1,
+            }
+        }
+
+        fn advance(&mut self, len: usize) {
+            for c in self.input[self.pos..self.pos + len].chars() {
+                if c == '\n' {
+                    self.line += 1;
+                    self.column = 1;
+                } else {
+                    self.column += 1;
                 }
-                Over => {
-                    let x2 = pop(stack)?;
-                    let x1 = pop(stack)?;
-                    stack.push(x1);
-                    stack.push(x2);
-                    stack.push(x1);
+            }
+            self.pos += len;
+        }
+
+        // text up to (not including) the delimiter, which is consumed as well
+        fn take_until(&mut self, delimiter: impl Fn(char) -> bool) -> (&'a str, Option<char>) {
+            let rest = &self.input[self.pos..];
+            let len = rest.find(delimiter).unwrap_or(rest.len());
+            let found = rest[len..].chars().next();
+            self.advance(len + found.map_or(0, char::len_utf8));
+            (&rest[..len], found)
+        }
+    }
+
+    impl<'a> Iterator for Tokenizer<'a> {
+        type Item = Lexeme<'a>;
+
+        fn next(&mut self) -> Option<Lexeme<'a>> {
+            loop {
+                let rest = &self.input[self.pos..];
+                self.advance(
+                    rest.len()
+                        - rest
+                            .trim_start_matches(|c: char| c.is_ascii_whitespace())
+                            .len(),
+                );
+                if self.pos == self.input.len() {
+                    return None;
                 }
-                ValueT(val) => stack.push(*val),
-                _ => unimplemented!(),
+
+                let (pos, line, column) = (self.pos, self.line, self.column);
+                let (word, delimiter) = self.take_until(|c| c.is_ascii_whitespace());
+                let token = match word {
+                    "(" => {
+                        self.take_until(|c| c == ')');
+                        continue;
+                    }
+                    "\\" => {
+                        if delimiter != Some('\n') {
+                            self.take_until(|c| c == '\n');
+                        }
+                        continue;
+                    }
+                    ".\"" => PrintString(self.take_until(|c| c == '"').0.to_string()),
+                    "s\"" | "S\"" => StringLiteral(self.take_until(|c| c == '"').0.to_string()),
+                    word => {
+                        return Some(Lexeme {
+                            span: Span {
+                                pos,
+                                line,
+                                column,
+                                text: word,
+                            },
+                            token: Token::from_str(word),
+                        })
+                    }
+                };
+                // string literals are located together with their text
+                return Some(Lexeme {
+                    span: Span {
+                        pos,
+                        line,
+                        column,
+                        text: &self.input[pos..self.pos],
+                    },
+                    token,
+                });
             }
+        }
+    }
 
-            Ok(())
+    impl Token {
+        pub fn from_str(s: &str) -> Self {
+            match s {
+                ":" => Colon,
+                ";" => Semicolon,
+                s => match s.to_lowercase().as_str() {
+                    "if" => If,
+                    "else" => Else,
+                    "then" => Then,
+                    "do" => Do,
+                    "loop" => Loop,
+                    "begin" => Begin,
+                    "until" => Until,
+                    "while" => While,
+                    "repeat" => Repeat,
+                    "variable" => Variable,
+                    "constant" => Constant,
+                    "see" => See,
+                    "forget" => Forget,
+                    "marker" => Marker,
+                    s => Word(s.to_string()),
+                },
+            }
         }
     }
 }
@@ -0,0 +1,74 @@
+use forth::Forth;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::process::exit;
+
+const USAGE: &str = "usage: forth [FILE]";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => repl(),
+        [arg] if arg == "-h" || arg == "--help" => println!("{}", USAGE),
+        [file] => run_file(file),
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    }
+}
+
+// evaluate whole script, so that definitions may span several lines
+fn run_file(file: &str) {
+    let source = match std::fs::read_to_string(file) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("forth: {}: {}", file, err);
+            exit(2);
+        }
+    };
+
+    if let Err(err) = Forth::new().eval(&source) {
+        eprintln!("forth: {}: {}", file, err);
+        exit(1);
+    }
+}
+
+fn repl() {
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            eprintln!("forth: {}", err);
+            exit(2);
+        }
+    };
+    let mut f = Forth::new();
+
+    loop {
+        match editor.readline("") {
+            Ok(line) => {
+                editor.add_history_entry(line.as_str()).ok();
+                // errors leave the interpreter usable, so just report them
+                match f.eval(&line) {
+                    Ok(()) => println!(" ok {}", show_stack(&f)),
+                    Err(err) => println!(" error: {} {}", err, show_stack(&f)),
+                }
+            }
+            // ctrl-c drops current line
+            Err(ReadlineError::Interrupted) => continue,
+            // ctrl-d ends the session
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("forth: {}", err);
+                exit(2);
+            }
+        }
+    }
+}
+
+// same format as `.s`: <depth> followed by values in the current base,
+// top of the stack last
+fn show_stack(f: &Forth) -> String {
+    let values: Vec<String> = f.stack().iter().map(|&x| f.format(x)).collect();
+    format!("<{}> {}", f.stack().len(), values.join(" "))
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "repl")]
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn script(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("forth-cli-{}-{}.fs", std::process::id(), name));
+    std::fs::write(&path, source).unwrap();
+    path
+}
+
+fn forth(args: &[&std::ffi::OsStr], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_forth"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn runs_script_file() {
+    let path = script("ok", ": f\n  3 0 do i . loop ;\nf cr\n");
+    let output = forth(&[path.as_os_str()], "");
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    assert_eq!("0 1 2 \n", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn script_error_sets_exit_code() {
+    let path = script("err", "1 .\n1 0 /\n2 .\n");
+    let output = forth(&[path.as_os_str()], "");
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("1 ", String::from_utf8_lossy(&output.stdout));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("division by zero"));
+}
+
+#[test]
+fn missing_script() {
+    let output = forth(&["/nonexistent/script.fs".as_ref()], "");
+    assert_eq!(Some(2), output.status.code());
+}
+
+#[test]
+fn interactive_session_keeps_state_after_errors() {
+    let output = forth(&[], "1 2\nfoo\n+ .s\n");
+    assert!(output.status.success());
+    assert_eq!(
+        " ok <2> 1 2\n error: unknown word at line 1, column 1 (foo) <2> 1 2\n<1> 3  ok <1> 3\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn interactive_session_shows_stack_in_current_base() {
+    let output = forth(&[], "255 hex\nff 10\ndecimal\n");
+    assert!(output.status.success());
+    assert_eq!(
+        " ok <1> ff\n ok <3> ff ff 10\n ok <3> 255 255 16\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
@@ -1,4 +1,6 @@
-use forth::{Error, Forth, Value};
+use forth::{Arithmetic, Error, ErrorKind, Forth, Limits, Value};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
 
 #[test]
 fn no_input_no_stack() {
@@ -22,8 +24,14 @@ fn can_add_two_numbers() {
 #[test]
 fn addition_error() {
     let mut f = Forth::new();
-    assert_eq!(Err(Error::StackUnderflow), f.eval("1 +"));
-    assert_eq!(Err(Error::StackUnderflow), f.eval("+"));
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("1 +").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("+").map_err(|e| e.kind())
+    );
 }
 
 #[test]
@@ -36,8 +44,14 @@ fn can_subtract_two_numbers() {
 #[test]
 fn subtraction_error() {
     let mut f = Forth::new();
-    assert_eq!(Err(Error::StackUnderflow), f.eval("1 -"));
-    assert_eq!(Err(Error::StackUnderflow), f.eval("-"));
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("1 -").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("-").map_err(|e| e.kind())
+    );
 }
 
 #[test]
@@ -50,8 +64,14 @@ fn can_multiply_two_numbers() {
 #[test]
 fn multiplication_error() {
     let mut f = Forth::new();
-    assert_eq!(Err(Error::StackUnderflow), f.eval("1 *"));
-    assert_eq!(Err(Error::StackUnderflow), f.eval("*"));
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("1 *").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("*").map_err(|e| e.kind())
+    );
 }
 
 #[test]
@@ -71,14 +91,23 @@ fn performs_integer_division() {
 #[test]
 fn division_error() {
     let mut f = Forth::new();
-    assert_eq!(Err(Error::StackUnderflow), f.eval("1 /"));
-    assert_eq!(Err(Error::StackUnderflow), f.eval("/"));
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("1 /").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("/").map_err(|e| e.kind())
+    );
 }
 
 #[test]
 fn errors_if_dividing_by_zero() {
     let mut f = Forth::new();
-    assert_eq!(Err(Error::DivisionByZero), f.eval("4 0 /"));
+    assert_eq!(
+        Err(ErrorKind::DivisionByZero),
+        f.eval("4 0 /").map_err(|e| e.kind())
+    );
 }
 
 #[test]
@@ -119,7 +148,10 @@ fn dup_case_insensitive() {
 #[test]
 fn dup_error() {
     let mut f = Forth::new();
-    assert_eq!(Err(Error::StackUnderflow), f.eval("dup"));
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("dup").map_err(|e| e.kind())
+    );
 }
 
 #[test]
@@ -146,7 +178,10 @@ fn drop_case_insensitive() {
 #[test]
 fn drop_error() {
     let mut f = Forth::new();
-    assert_eq!(Err(Error::StackUnderflow), f.eval("drop"));
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("drop").map_err(|e| e.kind())
+    );
 }
 
 #[test]
@@ -173,8 +208,14 @@ fn swap_case_insensitive() {
 #[test]
 fn swap_error() {
     let mut f = Forth::new();
-    assert_eq!(Err(Error::StackUnderflow), f.eval("1 swap"));
-    assert_eq!(Err(Error::StackUnderflow), f.eval("swap"));
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("1 swap").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("swap").map_err(|e| e.kind())
+    );
 }
 
 #[test]
@@ -201,8 +242,14 @@ fn over_case_insensitive() {
 #[test]
 fn over_error() {
     let mut f = Forth::new();
-    assert_eq!(Err(Error::StackUnderflow), f.eval("1 over"));
-    assert_eq!(Err(Error::StackUnderflow), f.eval("over"));
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("1 over").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("over").map_err(|e| e.kind())
+    );
 }
 
 // User-defined words
@@ -286,21 +333,36 @@ fn can_define_word_that_uses_word_with_the_same_name() {
 #[test]
 fn defining_a_number() {
     let mut f = Forth::new();
-    assert_eq!(Err(Error::InvalidWord), f.eval(": 1 2 ;"));
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval(": 1 2 ;").map_err(|e| e.kind())
+    );
 }
 
 #[test]
 fn malformed_word_definition() {
     let mut f = Forth::new();
-    assert_eq!(Err(Error::InvalidWord), f.eval(":"));
-    assert_eq!(Err(Error::InvalidWord), f.eval(": foo"));
-    assert_eq!(Err(Error::InvalidWord), f.eval(": foo 1"));
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval(":").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval(": foo").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval(": foo 1").map_err(|e| e.kind())
+    );
 }
 
 #[test]
 fn calling_non_existing_word() {
     let mut f = Forth::new();
-    assert_eq!(Err(Error::UnknownWord), f.eval("1 foo"));
+    assert_eq!(
+        Err(ErrorKind::UnknownWord),
+        f.eval("1 foo").map_err(|e| e.kind())
+    );
 }
 
 #[test]
@@ -327,3 +389,1614 @@ fn redefine_an_existing_word_with_another_existing_word() {
     assert!(f.eval("bar foo").is_ok());
     assert_eq!(vec![6, 6], f.stack());
 }
+
+// Control flow
+
+#[test]
+fn if_then_runs_body_on_true() {
+    let mut f = Forth::new();
+    assert!(f.eval(": f if 10 then 20 ;").is_ok());
+    assert!(f.eval("1 f 0 f").is_ok());
+    assert_eq!(vec![10, 20, 20], f.stack());
+}
+
+#[test]
+fn if_else_then() {
+    let mut f = Forth::new();
+    assert!(f.eval(": choose IF 1 ELSE 2 THEN ;").is_ok());
+    assert!(f.eval("-1 choose 0 choose").is_ok());
+    assert_eq!(vec![1, 2], f.stack());
+}
+
+#[test]
+fn nested_if() {
+    let mut f = Forth::new();
+    assert!(f.eval(": f if if 1 else 2 then else 3 then ;").is_ok());
+    assert!(f.eval("1 1 f 0 1 f 0 f").is_ok());
+    assert_eq!(vec![1, 2, 3], f.stack());
+}
+
+#[test]
+fn if_error() {
+    let mut f = Forth::new();
+    assert!(f.eval(": f if 1 then ;").is_ok());
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("f").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn do_loop_with_index() {
+    let mut f = Forth::new();
+    assert!(f.eval(": f 5 0 do i loop ;").is_ok());
+    assert!(f.eval("f").is_ok());
+    assert_eq!(vec![0, 1, 2, 3, 4], f.stack());
+}
+
+#[test]
+fn nested_do_loop_with_outer_index() {
+    let mut f = Forth::new();
+    assert!(f.eval(": f 3 1 do 2 0 do j i * loop loop ;").is_ok());
+    assert!(f.eval("f").is_ok());
+    assert_eq!(vec![0, 1, 0, 2], f.stack());
+}
+
+#[test]
+fn do_loop_body_runs_at_least_once() {
+    let mut f = Forth::new();
+    assert!(f.eval(": f 0 0 do 7 loop ;").is_ok());
+    assert!(f.eval("f").is_ok());
+    assert_eq!(vec![7], f.stack());
+}
+
+#[test]
+fn loop_calls_other_words() {
+    let mut f = Forth::new();
+    assert!(f.eval(": sq dup * ;").is_ok());
+    assert!(f.eval(": squares 4 1 do i sq loop ;").is_ok());
+    assert!(f.eval("squares").is_ok());
+    assert_eq!(vec![1, 4, 9], f.stack());
+}
+
+#[test]
+fn begin_until() {
+    let mut f = Forth::new();
+    assert!(f.eval(": f 0 begin 1 + dup 3 / until ;").is_ok());
+    assert!(f.eval("f").is_ok());
+    assert_eq!(vec![3], f.stack());
+}
+
+#[test]
+fn begin_while_repeat() {
+    let mut f = Forth::new();
+    assert!(f.eval(": f begin dup while dup 1 - repeat ;").is_ok());
+    assert!(f.eval("3 f").is_ok());
+    assert_eq!(vec![3, 2, 1, 0], f.stack());
+}
+
+#[test]
+fn control_flow_is_compile_only() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval("1 if 2 then").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn loop_index_outside_of_loop() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval("i").map_err(|e| e.kind())
+    );
+    assert!(f.eval(": f 1 0 do j loop ;").is_ok());
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval("f").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn unbalanced_control_flow() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval(": f if 1 ;").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval(": f 1 then ;").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval(": f begin 1 repeat ;").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval(": f 1 0 do if loop then ;").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn control_flow_words_are_not_redefinable() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval(": if 1 ;").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn deeply_nested_words() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": a 1 + ; : b a a ; : c b b ; : d c c ; : e d d ;")
+        .is_ok());
+    assert!(f
+        .eval(": f e e ; : g f f ; : h g g ; : i h h ; : j i i ;")
+        .is_ok());
+    assert!(f.eval("0 j").is_ok());
+    assert_eq!(vec![512], f.stack());
+}
+
+// Comparison and logic
+
+#[test]
+fn comparisons_return_forth_flags() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("1 1 = 1 2 = 1 2 <> 1 2 < 2 1 < 2 1 > 0 0= 5 0=")
+        .is_ok());
+    assert_eq!(vec![-1, 0, -1, -1, 0, -1, -1, 0], f.stack());
+}
+
+#[test]
+fn comparison_error() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("1 =").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("<").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("0=").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn bitwise_logic() {
+    let mut f = Forth::new();
+    assert!(f.eval("12 10 and 12 10 or 12 10 xor 0 invert").is_ok());
+    assert_eq!(vec![8, 14, 6, -1], f.stack());
+}
+
+#[test]
+fn logic_works_with_flags() {
+    let mut f = Forth::new();
+    assert!(f.eval(": between rot tuck > -rot < and ;").is_ok());
+    assert!(f.eval("5 1 10 between 15 1 10 between").is_ok());
+    assert_eq!(vec![-1, 0], f.stack());
+}
+
+#[test]
+fn logic_error() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("1 and").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("invert").map_err(|e| e.kind())
+    );
+}
+
+// Arithmetic
+
+#[test]
+fn mod_and_divmod() {
+    let mut f = Forth::new();
+    assert!(f.eval("7 3 mod 7 3 /mod -7 2 mod").is_ok());
+    assert_eq!(vec![1, 1, 2, -1], f.stack());
+}
+
+#[test]
+fn mod_errors() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("1 mod").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("/mod").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::DivisionByZero),
+        f.eval("1 0 mod").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::DivisionByZero),
+        f.eval("1 0 /mod").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn negate_abs_min_max() {
+    let mut f = Forth::new();
+    assert!(f.eval("5 negate -5 abs 3 4 min 3 4 max").is_ok());
+    assert_eq!(vec![-5, 5, 3, 4], f.stack());
+}
+
+#[test]
+fn unary_arithmetic_error() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("negate").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("abs").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("1 max").map_err(|e| e.kind())
+    );
+}
+
+// Extended stack manipulation
+
+#[test]
+fn rot_and_minus_rot() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2 3 rot").is_ok());
+    assert_eq!(vec![2, 3, 1], f.stack());
+    assert!(f.eval("-rot").is_ok());
+    assert_eq!(vec![1, 2, 3], f.stack());
+}
+
+#[test]
+fn nip_and_tuck() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2 nip 3 tuck").is_ok());
+    assert_eq!(vec![3, 2, 3], f.stack());
+}
+
+#[test]
+fn double_cell_words() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2 2dup 3 4 2swap 5 6 2drop").is_ok());
+    assert_eq!(vec![1, 2, 3, 4, 1, 2], f.stack());
+}
+
+#[test]
+fn pick_and_roll() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2 3 0 pick 3 pick").is_ok());
+    assert_eq!(vec![1, 2, 3, 3, 1], f.stack());
+    assert!(f.eval("4 roll").is_ok());
+    assert_eq!(vec![2, 3, 3, 1, 1], f.stack());
+}
+
+#[test]
+fn extended_stack_errors() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("1 2 rot").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("1 -rot").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("nip").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("tuck").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("2dup").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("2drop").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("1 2 3 2swap").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("1 pick").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("-1 roll").map_err(|e| e.kind())
+    );
+}
+
+// Variables, constants and memory
+
+#[test]
+fn variable_store_and_fetch() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable x 42 x ! x @").is_ok());
+    assert_eq!(vec![42], f.stack());
+}
+
+#[test]
+fn variables_keep_state_between_evals() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable counter").is_ok());
+    assert!(f.eval(": inc 1 counter +! ;").is_ok());
+    assert!(f.eval("inc inc").is_ok());
+    assert!(f.eval("inc counter @").is_ok());
+    assert_eq!(vec![3], f.stack());
+}
+
+#[test]
+fn variables_are_zero_initialized_and_distinct() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable a variable b 5 b ! a @ b @").is_ok());
+    assert_eq!(vec![0, 5], f.stack());
+}
+
+#[test]
+fn constant() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("10 constant ten : twenty ten ten + ; twenty")
+        .is_ok());
+    assert_eq!(vec![20], f.stack());
+}
+
+#[test]
+fn constant_error() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("constant ten").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval("1 constant").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval("variable 1").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn here_allot_and_comma() {
+    let mut f = Forth::new();
+    assert!(f.eval("here 3 allot here").is_ok());
+    assert_eq!(vec![0, 3], f.stack());
+    assert!(f.eval("7 , 8 , here 4 @ 3 @").is_ok());
+    assert_eq!(vec![0, 3, 5, 8, 7], f.stack());
+}
+
+#[test]
+fn huge_allot_fails_without_limits() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::MemoryLimitExceeded),
+        f.eval("2147483647 allot").map_err(|e| e.kind())
+    );
+    let mut f = Forth::<i64>::default();
+    assert_eq!(
+        Err(ErrorKind::MemoryLimitExceeded),
+        f.eval("4611686018427387904 allot").map_err(|e| e.kind())
+    );
+    assert!(f.eval("here").is_ok());
+    assert_eq!(vec![0], f.stack());
+}
+
+#[test]
+fn allot_over_what_host_can_allocate_fails() {
+    let mut f = Forth::<i64>::default();
+    f.set_limits(Limits {
+        max_memory: Some(usize::MAX),
+        ..Limits::default()
+    });
+    assert_eq!(
+        Err(ErrorKind::MemoryLimitExceeded),
+        f.eval("4611686018427387904 allot").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn allot_can_release_memory() {
+    let mut f = Forth::new();
+    assert!(f.eval("2 allot -1 allot here").is_ok());
+    assert_eq!(vec![1], f.stack());
+    assert_eq!(
+        Err(ErrorKind::InvalidAddress),
+        f.eval("-2 allot").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn memory_can_hold_arrays() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable arr 4 allot").is_ok());
+    assert!(f.eval(": fill 5 0 do i i * arr i + ! loop ;").is_ok());
+    assert!(f.eval("fill arr 3 + @ arr 4 + @").is_ok());
+    assert_eq!(vec![9, 16], f.stack());
+}
+
+#[test]
+fn invalid_address() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::InvalidAddress),
+        f.eval("0 @").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::InvalidAddress),
+        f.eval("variable x 1 x 1 + !").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::InvalidAddress),
+        f.eval("-1 @").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::InvalidAddress),
+        f.eval("1 100 +!").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn memory_word_errors() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("@").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("variable x x !").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval("allot").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::StackUnderflow),
+        f.eval(",").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn variable_is_not_allowed_in_definition() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval(": f variable x ;").map_err(|e| e.kind())
+    );
+}
+
+// Output
+
+fn output_of(input: &str) -> (Result<(), ErrorKind>, String) {
+    let mut out = Vec::new();
+    let result = Forth::with_output(&mut out)
+        .eval(input)
+        .map_err(|e| e.kind());
+    (result, String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn dot_prints_top_of_the_stack() {
+    assert_eq!((Ok(()), "3 -1 ".to_string()), output_of("-1 1 2 + . ."));
+}
+
+#[test]
+fn emit_and_cr() {
+    assert_eq!(
+        (Ok(()), "Hi\n".to_string()),
+        output_of("72 emit 105 emit cr")
+    );
+}
+
+#[test]
+fn print_string() {
+    assert_eq!(
+        (Ok(()), "Hello,  World!".to_string()),
+        output_of(".\" Hello,  World!\"")
+    );
+}
+
+#[test]
+fn print_string_in_definition() {
+    assert_eq!(
+        (Ok(()), "x=1 x=2 ".to_string()),
+        output_of(": show .\" x=\" . ; 1 show 2 show")
+    );
+}
+
+#[test]
+fn print_string_needs_space() {
+    assert_eq!((Ok(()), "a b".to_string()), output_of(".\" a b\""));
+    assert_eq!(
+        (Err(ErrorKind::UnknownWord), "".to_string()),
+        output_of(".\"ab\"")
+    );
+}
+
+#[test]
+fn unterminated_string_takes_rest_of_input() {
+    assert_eq!((Ok(()), "1 2".to_string()), output_of(".\" 1 2"));
+}
+
+#[test]
+fn print_stack() {
+    let mut out = Vec::new();
+    let mut f = Forth::with_output(&mut out);
+    assert!(f.eval("1 2 3 .s").is_ok());
+    assert_eq!(vec![1, 2, 3], f.stack());
+    std::mem::drop(f);
+    assert_eq!("<3> 1 2 3 ", String::from_utf8(out).unwrap());
+}
+
+#[test]
+fn output_words_in_loop() {
+    assert_eq!(
+        (Ok(()), "0 1 2 \n".to_string()),
+        output_of(": f 3 0 do i . loop cr ; f")
+    );
+}
+
+#[test]
+fn output_errors() {
+    assert_eq!(
+        (Err(ErrorKind::StackUnderflow), "".to_string()),
+        output_of(".")
+    );
+    assert_eq!(
+        (Err(ErrorKind::StackUnderflow), "".to_string()),
+        output_of("emit")
+    );
+}
+
+#[test]
+fn failing_output() {
+    struct Broken;
+    impl std::io::Write for Broken {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut f = Forth::with_output(Broken);
+    assert_eq!(
+        Err(ErrorKind::OutputFailed),
+        f.eval("1 .").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::OutputFailed),
+        f.eval(".\" x\"").map_err(|e| e.kind())
+    );
+}
+
+// Error diagnostics
+
+#[test]
+fn error_points_to_offending_token() {
+    let mut f = Forth::new();
+    let err = f.eval("1 2 + foo 3").unwrap_err();
+    assert_eq!(ErrorKind::UnknownWord, err.kind());
+    assert_eq!(Some(6), err.position());
+    assert_eq!(Some("foo"), err.word());
+    assert!(err.trace().is_empty());
+}
+
+#[test]
+fn error_keeps_original_spelling() {
+    let mut f = Forth::new();
+    let err = f.eval("1  DUP  Drop DROP DROP").unwrap_err();
+    assert_eq!(ErrorKind::StackUnderflow, err.kind());
+    assert_eq!(Some(18), err.position());
+    assert_eq!(Some("DROP"), err.word());
+    assert!(err.trace().is_empty());
+}
+
+#[test]
+fn error_in_primitive_has_no_trace() {
+    let mut f = Forth::new();
+    let err = f.eval("1 +").unwrap_err();
+    assert_eq!(ErrorKind::StackUnderflow, err.kind());
+    assert!(err.trace().is_empty());
+    assert_eq!("stack underflow at line 1, column 3 (+)", err.to_string());
+}
+
+#[test]
+fn error_trace_of_nested_definitions() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": inner 0 / ; : middle 1 inner ; : outer middle ;")
+        .is_ok());
+    let err = f.eval("10 outer").unwrap_err();
+    assert_eq!(ErrorKind::DivisionByZero, err.kind());
+    assert_eq!(Some(3), err.position());
+    assert_eq!(Some("outer"), err.word());
+    assert_eq!(
+        vec![
+            "outer".to_string(),
+            "middle".to_string(),
+            "inner".to_string()
+        ],
+        err.trace()
+    );
+}
+
+#[test]
+fn error_trace_after_return_from_word() {
+    let mut f = Forth::new();
+    assert!(f.eval(": ok 1 ; : bad ok ok + + ;").is_ok());
+    let err = f.eval("bad").unwrap_err();
+    assert_eq!(ErrorKind::StackUnderflow, err.kind());
+    assert_eq!(vec!["bad".to_string()], err.trace());
+}
+
+#[test]
+fn error_in_definition_points_into_definition() {
+    let mut f = Forth::new();
+    let err = f.eval(": foo 1 bar ;").unwrap_err();
+    assert_eq!(ErrorKind::UnknownWord, err.kind());
+    assert_eq!(Some(8), err.position());
+    assert_eq!(Some("bar"), err.word());
+
+    let err = f.eval(": foo if ; : 1 ;").unwrap_err();
+    assert_eq!(ErrorKind::InvalidWord, err.kind());
+    assert_eq!(Some(0), err.position());
+    assert_eq!(Some(":"), err.word());
+
+    let err = f.eval(": 1 2 ;").unwrap_err();
+    assert_eq!(Some(2), err.position());
+    assert_eq!(Some("1"), err.word());
+}
+
+#[test]
+fn error_display() {
+    let mut f = Forth::new();
+    assert!(f.eval(": f 0 / ;").is_ok());
+    let err = f.eval("1 f").unwrap_err();
+    assert_eq!(
+        "division by zero at line 1, column 3 (f) in f",
+        err.to_string()
+    );
+    let err: Error = ErrorKind::StackUnderflow.into();
+    assert_eq!("stack underflow", err.to_string());
+}
+
+// Execution limits
+
+fn limited(limits: Limits) -> Forth<'static> {
+    let mut f = Forth::new();
+    f.set_limits(limits);
+    f
+}
+
+#[test]
+fn no_limits_by_default() {
+    assert_eq!(Limits::default(), Forth::new().limits());
+    assert_eq!(None, Limits::default().max_steps);
+}
+
+#[test]
+fn step_limit_stops_infinite_loop() {
+    let mut f = limited(Limits {
+        max_steps: Some(1000),
+        ..Limits::default()
+    });
+    assert!(f.eval(": forever begin 0 until ;").is_ok());
+    let err = f.eval("forever").unwrap_err();
+    assert_eq!(ErrorKind::StepLimitExceeded, err.kind());
+    assert_eq!(vec!["forever".to_string()], err.trace());
+}
+
+#[test]
+fn step_limit_is_per_eval() {
+    let mut f = limited(Limits {
+        max_steps: Some(10),
+        ..Limits::default()
+    });
+    assert!(f.eval(": five 1 1 1 1 1 ;").is_ok());
+    assert!(f.eval("five five").is_ok());
+    assert!(f.eval("five five").is_ok());
+    assert_eq!(
+        Err(ErrorKind::StepLimitExceeded),
+        f.eval("five five five").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn stack_depth_limit() {
+    let mut f = limited(Limits {
+        max_stack_depth: Some(100),
+        ..Limits::default()
+    });
+    assert!(f.eval(": grow 1 begin dup 0 until ;").is_ok());
+    assert_eq!(
+        Err(ErrorKind::StackLimitExceeded),
+        f.eval("grow").map_err(|e| e.kind())
+    );
+    assert!(f.stack().len() <= 100);
+}
+
+#[test]
+fn failing_calls_do_not_grow_stack_over_limit() {
+    let mut f = limited(Limits {
+        max_stack_depth: Some(3),
+        ..Limits::default()
+    });
+    assert!(f.eval("1 2").is_ok());
+    for _ in 0..5 {
+        assert_eq!(
+            Err(ErrorKind::StackLimitExceeded),
+            f.eval("2dup").map_err(|e| e.kind())
+        );
+        assert!(f.stack().len() <= 3);
+    }
+}
+
+#[test]
+fn stack_depth_limit_applies_to_literals() {
+    let mut f = limited(Limits {
+        max_stack_depth: Some(2),
+        ..Limits::default()
+    });
+    assert!(f.eval("1 2").is_ok());
+    assert_eq!(
+        Err(ErrorKind::StackLimitExceeded),
+        f.eval("3").map_err(|e| e.kind())
+    );
+    assert_eq!(vec![1, 2], f.stack());
+}
+
+#[test]
+fn nesting_limit() {
+    let mut f = limited(Limits {
+        max_nesting: Some(3),
+        ..Limits::default()
+    });
+    assert!(f.eval(": a 1 ; : b a ; : c b ; : d c ;").is_ok());
+    assert!(f.eval("c").is_ok());
+    let err = f.eval("d").unwrap_err();
+    assert_eq!(ErrorKind::NestingLimitExceeded, err.kind());
+    assert_eq!(3, err.trace().len());
+}
+
+#[test]
+fn memory_limit() {
+    let mut f = limited(Limits {
+        max_memory: Some(4),
+        ..Limits::default()
+    });
+    assert!(f.eval("variable x 2 allot 7 ,").is_ok());
+    assert_eq!(
+        Err(ErrorKind::MemoryLimitExceeded),
+        f.eval("1 allot").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::MemoryLimitExceeded),
+        f.eval("1 ,").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::MemoryLimitExceeded),
+        f.eval("variable y").map_err(|e| e.kind())
+    );
+    assert!(f.eval("-1 allot 8 , here").is_ok());
+    assert_eq!(vec![4], f.stack());
+}
+
+#[test]
+fn interpreter_is_usable_after_limit_is_exceeded() {
+    let mut f = limited(Limits {
+        max_steps: Some(100),
+        max_nesting: Some(2),
+        ..Limits::default()
+    });
+    assert!(f
+        .eval(": spin begin 0 until ; : deep spin ; : deeper deep ;")
+        .is_ok());
+    assert!(f.eval("deeper").is_err());
+    assert!(f.eval("spin").is_err());
+    f.set_limits(Limits::default());
+    assert!(f.eval("1 2 + : ok 3 ; ok").is_ok());
+    assert_eq!(vec![3, 3], f.stack());
+}
+
+// Transactional evaluation
+
+#[test]
+fn eval_leaves_partial_results_on_error() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2 : foo 3 ; foo bar").is_err());
+    assert_eq!(vec![1, 2, 3], f.stack());
+    assert!(f.eval("foo").is_ok());
+}
+
+#[test]
+fn eval_atomic_rolls_back_stack() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 2").is_ok());
+    assert_eq!(
+        Err(ErrorKind::DivisionByZero),
+        f.eval_atomic("drop 3 4 5 0 /").map_err(|e| e.kind())
+    );
+    assert_eq!(vec![1, 2], f.stack());
+}
+
+#[test]
+fn eval_atomic_rolls_back_definitions() {
+    let mut f = Forth::new();
+    assert!(f.eval(": foo 1 ;").is_ok());
+    assert!(f.eval_atomic(": foo 2 ; : bar 3 ; oops").is_err());
+    assert!(f.eval("foo").is_ok());
+    assert_eq!(vec![1], f.stack());
+    assert_eq!(
+        Err(ErrorKind::UnknownWord),
+        f.eval("bar").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn eval_atomic_rolls_back_memory() {
+    let mut f = Forth::new();
+    assert!(f.eval("variable x 5 x !").is_ok());
+    assert!(f.eval_atomic("7 x ! variable y 10 allot 1 0 /").is_err());
+    assert!(f.eval("x @ here").is_ok());
+    assert_eq!(vec![5, 1], f.stack());
+}
+
+#[test]
+fn eval_atomic_keeps_successful_changes() {
+    let mut f = Forth::new();
+    assert!(f.eval_atomic(": sq dup * ; 3 sq").is_ok());
+    assert!(f.eval_atomic("sq").is_ok());
+    assert_eq!(vec![81], f.stack());
+}
+
+#[test]
+fn snapshot_and_restore() {
+    let mut f = Forth::new();
+    assert!(f.eval(": foo 1 ; variable x 4 x ! 10 20").is_ok());
+    let checkpoint = f.snapshot();
+    assert!(f.eval("drop : foo 2 ; 9 x ! 30 foo").is_ok());
+    assert_eq!(vec![10, 30, 2], f.stack());
+
+    f.restore(checkpoint.clone());
+    assert_eq!(vec![10, 20], f.stack());
+    assert!(f.eval("foo x @").is_ok());
+    assert_eq!(vec![10, 20, 1, 4], f.stack());
+
+    f.restore(checkpoint);
+    assert_eq!(vec![10, 20], f.stack());
+}
+
+#[test]
+fn restore_does_not_affect_limits() {
+    let mut f = Forth::new();
+    let checkpoint = f.snapshot();
+    let limits = Limits {
+        max_steps: Some(5),
+        ..Limits::default()
+    };
+    f.set_limits(limits);
+    f.restore(checkpoint);
+    assert_eq!(limits, f.limits());
+}
+
+// Cell types and arithmetic modes
+
+fn with_arithmetic(arithmetic: Arithmetic) -> Forth<'static> {
+    let mut f = Forth::new();
+    f.set_arithmetic(arithmetic);
+    f
+}
+
+#[test]
+fn checked_arithmetic_by_default() {
+    let f = Forth::new();
+    assert_eq!(Arithmetic::Checked, f.arithmetic());
+}
+
+#[test]
+fn checked_overflow() {
+    let mut f = Forth::new();
+    for input in &[
+        "2147483647 1 +",
+        "-2147483648 1 -",
+        "65536 65536 *",
+        "-2147483648 -1 /",
+        "-2147483648 -1 mod",
+        "-2147483648 -1 /mod",
+        "-2147483648 negate",
+        "-2147483648 abs",
+        "variable x 2147483647 x ! 1 x +!",
+    ] {
+        assert_eq!(
+            Err(ErrorKind::Overflow),
+            f.eval(input).map_err(|e| e.kind()),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn overflow_error_location() {
+    let mut f = Forth::new();
+    let err = f.eval(": inc 1 + ; 2147483647 inc").unwrap_err();
+    assert_eq!(ErrorKind::Overflow, err.kind());
+    assert_eq!(Some("inc"), err.word());
+    assert_eq!(&["inc"], err.trace());
+}
+
+#[test]
+fn division_by_zero_is_not_overflow() {
+    let mut f = with_arithmetic(Arithmetic::Wrapping);
+    assert_eq!(
+        Err(ErrorKind::DivisionByZero),
+        f.eval("1 0 /").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn division_by_zero_is_checked_before_dividend() {
+    for word in &["/", "mod", "/mod"] {
+        let mut f = Forth::new();
+        assert_eq!(
+            Err(ErrorKind::DivisionByZero),
+            f.eval(&format!("0 {}", word)).map_err(|e| e.kind()),
+            "{}",
+            word
+        );
+    }
+}
+
+#[test]
+fn wrapping_arithmetic() {
+    let mut f = with_arithmetic(Arithmetic::Wrapping);
+    assert!(f
+        .eval("2147483647 1 + -2147483648 1 - 65536 65536 * -2147483648 -1 / -2147483648 negate")
+        .is_ok());
+    assert_eq!(
+        vec![Value::MIN, Value::MAX, 0, Value::MIN, Value::MIN],
+        f.stack()
+    );
+}
+
+#[test]
+fn saturating_arithmetic() {
+    let mut f = with_arithmetic(Arithmetic::Saturating);
+    assert!(f
+        .eval("2147483647 1 + -2147483648 1 - 65536 -65536 * -2147483648 -1 / -2147483648 abs")
+        .is_ok());
+    assert_eq!(
+        vec![Value::MAX, Value::MIN, Value::MIN, Value::MAX, Value::MAX],
+        f.stack()
+    );
+}
+
+#[test]
+fn min_mod_minus_one_is_zero_unless_checked() {
+    let mut f = with_arithmetic(Arithmetic::Saturating);
+    assert!(f.eval("-2147483648 -1 /mod").is_ok());
+    assert_eq!(vec![0, Value::MAX], f.stack());
+}
+
+#[test]
+fn i64_cells() {
+    let mut f = Forth::<i64>::default();
+    assert!(f.eval("2147483647 1 + 2147483648 *").is_ok());
+    assert_eq!(vec![1i64 << 62], f.stack());
+}
+
+#[test]
+fn i64_overflow() {
+    let mut f = Forth::<i64>::default();
+    assert_eq!(
+        Err(ErrorKind::Overflow),
+        f.eval("9223372036854775807 1 +").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn i128_cells() {
+    let mut f = Forth::<i128>::default();
+    assert!(f
+        .eval(": sq dup * ; 9223372036854775808 sq 1 - variable x x ! x @")
+        .is_ok());
+    assert_eq!(vec![(1i128 << 126) - 1], f.stack());
+}
+
+#[test]
+fn number_too_big_for_cell_is_unknown_word() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::UnknownWord),
+        f.eval("4294967296").map_err(|e| e.kind())
+    );
+    let mut f = Forth::<i64>::default();
+    assert!(f.eval("4294967296").is_ok());
+    assert_eq!(vec![4_294_967_296], f.stack());
+}
+
+#[test]
+fn generic_interpreter_with_output() {
+    let mut out = Vec::new();
+    {
+        let mut f = Forth::<i128>::with_output_cell(&mut out);
+        f.set_arithmetic(Arithmetic::Saturating);
+        assert!(f
+            .eval("170141183460469231731687303715884105727 1 + .")
+            .is_ok());
+    }
+    assert_eq!(
+        "170141183460469231731687303715884105727 ",
+        String::from_utf8(out).unwrap()
+    );
+}
+
+// Dictionary introspection
+
+#[test]
+fn words_lists_visible_words_most_recent_first() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": foo 1 ; variable bar : foo 2 ; 5 constant baz")
+        .is_ok());
+    let words = f.words();
+    assert_eq!(&["baz", "foo", "bar"], &words[..3]);
+    assert_eq!(1, words.iter().filter(|&&w| w == "foo").count());
+    assert!(words.contains(&"dup"));
+    assert!(words.contains(&"words"));
+}
+
+#[test]
+fn words_prints_dictionary() {
+    let (result, out) = output_of(": foo 1 ; : bar 2 ; words");
+    assert_eq!(Ok(()), result);
+    assert!(out.starts_with("bar foo "));
+    assert!(out.contains(" words "));
+    assert!(out.contains(" dup "));
+}
+
+#[test]
+fn see_prints_source() {
+    assert_eq!(
+        (Ok(()), ": sq dup * ;\n".to_string()),
+        output_of(": SQ DUP * ; see sq")
+    );
+}
+
+#[test]
+fn see_keeps_string_literals_verbatim() {
+    assert_eq!(
+        (Ok(()), ": hi .\"  Hello,  world\" cr ;\n".to_string()),
+        output_of(": hi .\"  Hello,  world\" cr ; see hi")
+    );
+}
+
+#[test]
+fn see_unknown_word() {
+    assert_eq!(Err(ErrorKind::UnknownWord), output_of("see foo").0);
+}
+
+#[test]
+fn see_is_not_allowed_in_definitions() {
+    assert_eq!(Err(ErrorKind::InvalidWord), output_of(": foo see dup ;").0);
+}
+
+#[test]
+fn definitions_of_all_kinds_of_words() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": empty ; : sq dup * ; 42 constant answer variable x marker here-be-dragons")
+        .is_ok());
+    assert_eq!(Some(": empty ;".to_string()), f.definition("empty"));
+    assert_eq!(Some(": sq dup * ;".to_string()), f.definition("sq"));
+    assert_eq!(
+        Some("42 constant answer".to_string()),
+        f.definition("answer")
+    );
+    assert_eq!(Some("variable x".to_string()), f.definition("X"));
+    assert_eq!(
+        Some("marker here-be-dragons".to_string()),
+        f.definition("here-be-dragons")
+    );
+    assert_eq!(Some("primitive dup".to_string()), f.definition("dup"));
+    assert_eq!(None, f.definition("nope"));
+}
+
+#[test]
+fn definition_of_shadowed_word() {
+    let mut f = Forth::new();
+    assert!(f.eval(": foo 1 ; : bar foo ; : foo 2 ;").is_ok());
+    assert_eq!(Some(": foo 2 ;".to_string()), f.definition("foo"));
+    assert_eq!(Some(": bar foo ;".to_string()), f.definition("bar"));
+}
+
+#[test]
+fn forget_removes_word_and_later_definitions() {
+    let mut f = Forth::new();
+    assert!(f.eval(": a 1 ; : b 2 ; : c 3 ; forget b a").is_ok());
+    assert_eq!(vec![1], f.stack());
+    for word in &["b", "c"] {
+        assert_eq!(
+            Err(ErrorKind::UnknownWord),
+            f.eval(word).map_err(|e| e.kind())
+        );
+    }
+}
+
+#[test]
+fn forget_uncovers_shadowed_definition() {
+    let mut f = Forth::new();
+    assert!(f.eval(": foo 1 ; : foo 2 ; forget foo foo").is_ok());
+    assert_eq!(vec![1], f.stack());
+}
+
+#[test]
+fn forget_uncovers_builtin() {
+    let mut f = Forth::new();
+    assert!(f.eval(": dup 5 ; forget dup 1 dup").is_ok());
+    assert_eq!(vec![1, 1], f.stack());
+}
+
+#[test]
+fn earlier_words_survive_forget() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": foo 1 ; : bar foo foo ; : baz 3 ; forget baz bar")
+        .is_ok());
+    assert_eq!(vec![1, 1], f.stack());
+}
+
+#[test]
+fn forget_releases_memory() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval("variable x variable y 3 allot forget y here")
+        .is_ok());
+    assert_eq!(vec![1], f.stack());
+}
+
+#[test]
+fn cannot_forget_builtin() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval("forget dup").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn cannot_forget_unknown_word() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::UnknownWord),
+        f.eval("forget foo").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn forget_releases_string_literals() {
+    assert_eq!(
+        (Ok(()), "bye".to_string()),
+        output_of(": hi .\" hello\" ; forget hi : bye .\" bye\" ; bye")
+    );
+}
+
+#[test]
+fn marker_forgets_everything_defined_after_it() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": keep 1 ; marker reset : foo 2 ; variable x 10 allot reset keep here")
+        .is_ok());
+    assert_eq!(vec![1, 0], f.stack());
+    for word in &["foo", "x", "reset"] {
+        assert_eq!(
+            Err(ErrorKind::UnknownWord),
+            f.eval(word).map_err(|e| e.kind())
+        );
+    }
+}
+
+#[test]
+fn marker_can_not_be_used_in_definitions() {
+    let mut f = Forth::new();
+    assert!(f.eval("marker reset").is_ok());
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval(": foo reset ;").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval(": foo marker bar ;").map_err(|e| e.kind())
+    );
+}
+
+// Native words
+
+fn pop(stack: &mut Vec<Value>) -> forth::Result<Value> {
+    stack.pop().ok_or_else(|| ErrorKind::StackUnderflow.into())
+}
+
+#[test]
+fn native_word() {
+    let mut f = Forth::new();
+    f.register_native("sensor", |stack| {
+        stack.push(42);
+        Ok(())
+    })
+    .unwrap();
+    assert!(f.eval("sensor SENSOR").is_ok());
+    assert_eq!(vec![42, 42], f.stack());
+}
+
+#[test]
+fn native_word_in_definition() {
+    let mut f = Forth::new();
+    f.register_native("double", |stack| {
+        let x = pop(stack)?;
+        stack.push(2 * x);
+        Ok(())
+    })
+    .unwrap();
+    assert!(f.eval(": quad double double ; 3 quad").is_ok());
+    assert_eq!(vec![12], f.stack());
+}
+
+#[test]
+fn native_word_names_follow_definition_rules() {
+    let mut f = Forth::new();
+    for name in &[
+        "",
+        "two words",
+        " pad",
+        "42",
+        "-1",
+        "if",
+        ";",
+        ":",
+        "(",
+        ".\"",
+        "s\"",
+    ] {
+        assert_eq!(
+            Err(ErrorKind::InvalidWord),
+            f.register_native(name, |_| Ok(())).map_err(|e| e.kind()),
+            "{:?}",
+            name
+        );
+    }
+    assert!(f.eval("hex").is_ok());
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.register_native("add", |_| Ok(())).map_err(|e| e.kind())
+    );
+    assert!(!f.words().contains(&"add"));
+}
+
+#[test]
+fn native_word_with_host_state() {
+    let mut f = Forth::new();
+    let next_id = Arc::new(AtomicI32::new(100));
+    let id = Arc::clone(&next_id);
+    f.register_native("new-id", move |stack| {
+        stack.push(id.fetch_add(1, Ordering::Relaxed));
+        Ok(())
+    })
+    .unwrap();
+    assert!(f.eval(": two-ids new-id new-id ; two-ids new-id").is_ok());
+    assert_eq!(vec![100, 101, 102], f.stack());
+    assert_eq!(103, next_id.load(Ordering::Relaxed));
+}
+
+#[test]
+fn native_word_error() {
+    let mut f = Forth::new();
+    f.register_native("double", |stack| {
+        let x = pop(stack)?;
+        stack.push(2 * x);
+        Ok(())
+    })
+    .unwrap();
+    let err = f.eval(": quad double double ; quad").unwrap_err();
+    assert_eq!(ErrorKind::StackUnderflow, err.kind());
+    assert_eq!(Some("quad"), err.word());
+    assert_eq!(&["quad"], err.trace());
+}
+
+#[test]
+fn native_word_shadows_builtin() {
+    let mut f = Forth::new();
+    f.register_native("dup", |stack| {
+        stack.push(0);
+        Ok(())
+    })
+    .unwrap();
+    assert!(f.eval("1 dup").is_ok());
+    assert_eq!(vec![1, 0], f.stack());
+}
+
+#[test]
+fn native_word_can_be_redefined() {
+    let mut f = Forth::new();
+    f.register_native("answer", |stack| {
+        stack.push(42);
+        Ok(())
+    })
+    .unwrap();
+    assert!(f.eval(": old answer ; : answer 7 ; old answer").is_ok());
+    assert_eq!(vec![42, 7], f.stack());
+}
+
+#[test]
+fn native_word_redefinition_does_not_affect_earlier_definitions() {
+    let mut f = Forth::new();
+    assert!(f.eval(": answer 7 ; : old answer ;").is_ok());
+    f.register_native("answer", |stack| {
+        stack.push(42);
+        Ok(())
+    })
+    .unwrap();
+    assert!(f.eval("old answer").is_ok());
+    assert_eq!(vec![7, 42], f.stack());
+}
+
+#[test]
+fn native_word_can_be_forgotten() {
+    let mut f = Forth::new();
+    f.register_native("answer", |stack| {
+        stack.push(42);
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(Some("native answer".to_string()), f.definition("answer"));
+    assert!(f.eval("forget answer").is_ok());
+    assert_eq!(
+        Err(ErrorKind::UnknownWord),
+        f.eval("answer").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn native_word_with_wide_cells() {
+    let mut f = Forth::<i128>::default();
+    f.register_native("big", |stack| {
+        stack.push(i128::MAX);
+        Ok(())
+    })
+    .unwrap();
+    assert!(f.eval("big").is_ok());
+    assert_eq!(vec![i128::MAX], f.stack());
+}
+
+// Comments, strings and number bases
+
+#[test]
+fn paren_comment() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 ( two ) 3 : foo ( n -- n n ) dup ; foo").is_ok());
+    assert_eq!(vec![1, 3, 3], f.stack());
+}
+
+#[test]
+fn paren_comment_spans_lines() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 ( first\nsecond ) 2").is_ok());
+    assert_eq!(vec![1, 2], f.stack());
+}
+
+#[test]
+fn unterminated_paren_comment_ends_with_input() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 ( 2 3").is_ok());
+    assert_eq!(vec![1], f.stack());
+}
+
+#[test]
+fn backslash_comment() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 \\ 2 3\n4 \\\n5").is_ok());
+    assert_eq!(vec![1, 4, 5], f.stack());
+}
+
+#[test]
+fn multi_line_definition() {
+    let mut f = Forth::new();
+    assert!(f
+        .eval(": sq ( n -- n*n )\n  dup * ; \\ square\n3 sq")
+        .is_ok());
+    assert_eq!(vec![9], f.stack());
+    assert_eq!(Some(": sq dup * ;".to_string()), f.definition("sq"));
+}
+
+#[test]
+fn string_literal_pushes_address_and_length() {
+    let mut f = Forth::new();
+    assert!(f.eval("s\" ab\" over @ rot 1 + @").is_ok());
+    assert_eq!(vec![2, 97, 98], f.stack());
+}
+
+#[test]
+fn type_prints_string() {
+    assert_eq!(
+        (Ok(()), "Hello, world!".to_string()),
+        output_of("S\" Hello, world!\" type")
+    );
+}
+
+#[test]
+fn string_literal_in_definition() {
+    assert_eq!(
+        (Ok(()), "hi hi ".to_string()),
+        output_of(": greet s\" hi \" type ; greet greet")
+    );
+}
+
+#[test]
+fn string_literal_in_definition_is_stored_once() {
+    let mut f = Forth::new();
+    assert!(f.eval(": s s\" abc\" ; here s s").is_ok());
+    assert_eq!(vec![3, 0, 3, 0, 3], f.stack());
+}
+
+#[test]
+fn string_literal_memory_limit() {
+    let mut f = limited(Limits {
+        max_memory: Some(2),
+        ..Limits::default()
+    });
+    assert_eq!(
+        Err(ErrorKind::MemoryLimitExceeded),
+        f.eval("s\" abc\"").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::MemoryLimitExceeded),
+        f.eval(": foo s\" abc\" ;").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn type_invalid_address() {
+    assert_eq!(Err(ErrorKind::InvalidAddress), output_of("0 1 type").0);
+    assert_eq!(
+        Err(ErrorKind::InvalidAddress),
+        output_of("s\" ab\" 1 + type").0
+    );
+}
+
+#[test]
+fn error_line_and_column() {
+    let mut f = Forth::new();
+    let err = f.eval("1 2\n  3 foo").unwrap_err();
+    assert_eq!(Some(2), err.line());
+    assert_eq!(Some(5), err.column());
+    assert_eq!(Some(8), err.position());
+    assert_eq!(Some("foo"), err.word());
+    assert_eq!("unknown word at line 2, column 5 (foo)", err.to_string());
+}
+
+#[test]
+fn error_column_counts_characters() {
+    let mut f = Forth::new();
+    let err = f.eval(": ä 1 ;\n ä ä bar").unwrap_err();
+    assert_eq!(Some(2), err.line());
+    assert_eq!(Some(6), err.column());
+}
+
+#[test]
+fn error_location_in_multi_line_definition() {
+    let mut f = Forth::new();
+    let err = f.eval(": foo\n  1 bar\n;").unwrap_err();
+    assert_eq!(ErrorKind::UnknownWord, err.kind());
+    assert_eq!((Some(2), Some(5)), (err.line(), err.column()));
+}
+
+#[test]
+fn number_prefixes() {
+    let mut f = Forth::new();
+    assert!(f.eval("$ff $FF #10 %101 $-10 #-7 %-1").is_ok());
+    assert_eq!(vec![255, 255, 10, 5, -16, -7, -1], f.stack());
+}
+
+#[test]
+fn prefix_alone_is_a_word() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::UnknownWord),
+        f.eval("$").map_err(|e| e.kind())
+    );
+    assert_eq!(
+        Err(ErrorKind::UnknownWord),
+        f.eval("%2").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn base_variable() {
+    let mut f = Forth::new();
+    assert!(f.eval("base @ 16 base ! ff base @ 2 base ! 101").is_ok());
+    assert_eq!(vec![10, 255, 16, 5], f.stack());
+}
+
+#[test]
+fn hex_and_decimal() {
+    let mut f = Forth::new();
+    assert!(f.eval("hex ff -a decimal 10").is_ok());
+    assert_eq!(vec![255, -10, 10], f.stack());
+}
+
+#[test]
+fn numbers_in_definitions_use_base_at_compile_time() {
+    let mut f = Forth::new();
+    assert!(f.eval("hex : foo 10 ; decimal foo 10").is_ok());
+    assert_eq!(vec![16, 10], f.stack());
+}
+
+#[test]
+fn words_which_are_numbers_in_current_base_can_not_be_defined() {
+    let mut f = Forth::new();
+    assert_eq!(
+        Err(ErrorKind::InvalidWord),
+        f.eval("hex : add 1 ;").map_err(|e| e.kind())
+    );
+}
+
+#[test]
+fn words_shadow_numbers_of_current_base() {
+    let mut f = Forth::new();
+    assert!(f.eval(": face 1 ; : a 2 ; hex face a b").is_ok());
+    assert!(f.eval(": g face a b ; g").is_ok());
+    assert_eq!(vec![1, 2, 11, 1, 2, 11], f.stack());
+}
+
+#[test]
+fn invalid_base_only_allows_prefixed_numbers() {
+    let mut f = Forth::new();
+    assert!(f.eval("1 base !").is_ok());
+    assert_eq!(
+        Err(ErrorKind::UnknownWord),
+        f.eval("10").map_err(|e| e.kind())
+    );
+    assert!(f.eval("#10 base ! 10").is_ok());
+    assert_eq!(vec![10], f.stack());
+}
+
+#[test]
+fn dot_prints_in_current_base() {
+    assert_eq!(
+        (Ok(()), "ff -ff <2> ff -ff 11 ".to_string()),
+        output_of("255 hex dup . dup negate . dup negate .s 2drop 3 2 base ! .")
+    );
+}
+
+#[test]
+fn extreme_numbers_in_other_bases() {
+    let mut f = Forth::new();
+    assert!(f.eval("$-80000000 $7fffffff").is_ok());
+    assert_eq!(vec![Value::MIN, Value::MAX], f.stack());
+    assert_eq!(
+        (Ok(()), "-80000000 ".to_string()),
+        output_of("-2147483648 hex .")
+    );
+}